@@ -0,0 +1,169 @@
+//! Compile the vanilla game tables in `tables/` into Rust constants.
+
+use std::{env, fmt::Write, fs, path::Path};
+
+/// Tab separated rows of a table, without comments and blank lines.
+fn rows(name: &str) -> Vec<Vec<String>> {
+    let path = Path::new("tables").join(name);
+    println!("cargo:rerun-if-changed={}", path.display());
+    let text = fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+    text.lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            line.split('\t')
+                .map(|cell| cell.trim().to_owned())
+                .collect()
+        })
+        .collect()
+}
+
+fn cell(row: &[String], column: usize) -> &str {
+    row.get(column).map_or("", String::as_str)
+}
+
+fn int(row: &[String], column: usize) -> i32 {
+    cell(row, column)
+        .parse()
+        .unwrap_or_else(|_| panic!("expected a number in column {column} of {row:?}"))
+}
+
+/// Space separated numbers, as the items of a Rust array.
+fn list(row: &[String], column: usize) -> String {
+    cell(row, column)
+        .split_whitespace()
+        .map(|n| {
+            n.parse::<i32>()
+                .unwrap_or_else(|_| panic!("expected numbers in column {column} of {row:?}"))
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn main() {
+    let mut out = String::new();
+
+    let stages = rows("stages.tsv");
+    for (i, row) in stages.iter().enumerate() {
+        assert_eq!(
+            int(row, 0),
+            i as i32,
+            "stages.tsv should list every map in order"
+        );
+    }
+    writeln!(out, "pub const STAGES: &[&str] = &[").unwrap();
+    for row in &stages {
+        writeln!(out, "    {:?},", cell(row, 1)).unwrap();
+    }
+    writeln!(out, "];").unwrap();
+
+    let levels = rows("arms_level.tsv");
+    for (i, row) in levels.iter().enumerate() {
+        assert_eq!(
+            int(row, 0),
+            i as i32,
+            "arms_level.tsv should list every weapon in order"
+        );
+    }
+    writeln!(out, "pub const ARMS_LEVELS: &[[i32; 3]] = &[").unwrap();
+    for row in &levels {
+        writeln!(
+            out,
+            "    [{}, {}, {}],",
+            int(row, 1),
+            int(row, 2),
+            int(row, 3)
+        )
+        .unwrap();
+    }
+    writeln!(out, "];").unwrap();
+
+    let ammo = rows("arms_ammo.tsv");
+    writeln!(out, "pub const ARMS_AMMO: &[(i32, i32, i32)] = &[").unwrap();
+    for row in &ammo {
+        writeln!(
+            out,
+            "    ({}, {}, {}),",
+            int(row, 0),
+            int(row, 1),
+            int(row, 2)
+        )
+        .unwrap();
+    }
+    writeln!(out, "];").unwrap();
+
+    writeln!(out, "pub const COLLECTIBLES: &[(i32, u16, Pickup)] = &[").unwrap();
+    for row in rows("collectibles.tsv") {
+        let amount = |default: i32| match cell(&row, 4) {
+            "" => default,
+            _ => int(&row, 4),
+        };
+        let pickup = match cell(&row, 2) {
+            // unknown, see `Pickup::Life`
+            "life" => format!("Pickup::Life({})", amount(0)),
+            "weapon" => {
+                let weapon = int(&row, 3);
+                let expansion = ammo
+                    .iter()
+                    .find(|ammo| int(ammo, 0) == weapon)
+                    .map_or(0, |ammo| int(ammo, 2));
+                format!(
+                    "Pickup::Weapon {{ weapon: {weapon}, ammo: {} }}",
+                    amount(expansion)
+                )
+            }
+            "item" => format!("Pickup::Item({})", int(&row, 3)),
+            kind => panic!("unknown collectible kind {kind:?} in {row:?}"),
+        };
+        writeln!(out, "    ({}, {}, {pickup}),", int(&row, 0), int(&row, 1)).unwrap();
+    }
+    writeln!(out, "];").unwrap();
+
+    writeln!(out, "pub const TRADES: &[Trade] = &[").unwrap();
+    for row in rows("trades.tsv") {
+        writeln!(
+            out,
+            "    Trade {{ from: {}, to: {}, ammo: {}, flags_set: &[{}], flags_cleared: &[{}], items_added: &[{}], items_removed: &[{}] }},",
+            int(&row, 0),
+            int(&row, 1),
+            int(&row, 2),
+            list(&row, 3),
+            list(&row, 4),
+            list(&row, 5),
+            list(&row, 6),
+        )
+        .unwrap();
+    }
+    writeln!(out, "];").unwrap();
+
+    writeln!(out, "pub const BOSS_DEATH_EVENTS: &[(i32, u16)] = &[").unwrap();
+    for row in rows("bosses.tsv") {
+        writeln!(out, "    ({}, {}),", int(&row, 0), int(&row, 1)).unwrap();
+    }
+    writeln!(out, "];").unwrap();
+
+    writeln!(out, "pub const BOSSES: &[(i32, u16, &str)] = &[").unwrap();
+    for row in rows("boss_flags.tsv") {
+        writeln!(
+            out,
+            "    ({}, {}, {:?}),",
+            int(&row, 0),
+            int(&row, 1),
+            cell(&row, 2)
+        )
+        .unwrap();
+    }
+    writeln!(out, "];").unwrap();
+
+    let descriptions = rows("descriptions.tsv");
+    for (kind, name) in [("weapon", "WEAPONS"), ("item", "ITEMS")] {
+        writeln!(out, "pub const {name}: &[(i32, &str)] = &[").unwrap();
+        for row in descriptions.iter().filter(|row| cell(row, 0) == kind) {
+            writeln!(out, "    ({}, {:?}),", int(row, 1), cell(row, 2)).unwrap();
+        }
+        writeln!(out, "];").unwrap();
+    }
+
+    let dest = Path::new(&env::var("OUT_DIR").unwrap()).join("vanilla.rs");
+    fs::write(dest, out).unwrap();
+}
@@ -1,14 +1,28 @@
 use cavestory_save::{GameProfile, Profile};
 
-use cavestory_save::items::*;
-use cavestory_save::strum::IntoEnumIterator;
 use egui::{Context, Panel, Ui};
 
 use storage::StorageIO;
 
-use self::utils::ProfileExt;
+use self::utils::{
+    raw::{EquipBits, Flags, GameId, Stars, UnknownIds},
+    ProfileExt,
+};
 
+mod config;
+mod data;
+mod flags;
+mod icons;
+mod music;
+mod nikumaru;
+mod picker;
+mod preview;
+mod script;
+mod settings;
+mod slots;
 mod storage;
+mod trade;
+mod warp;
 
 #[derive(Default)]
 pub struct MainApp {
@@ -16,12 +30,40 @@ pub struct MainApp {
     profile: Option<(Profile, GameProfile)>,
     weapon_num: usize,
     inventory_num: usize,
-    equip_checked: [bool; 9],
+    equip_bits: EquipBits,
+    stars: Stars,
+    unknown: UnknownIds,
+    flags: Flags,
+    data_dirs: data::DataDirs,
+    nikumaru: nikumaru::NikumaruEditor,
+    config: config::ConfigEditor,
+    settings: settings::SettingsEditor,
+    map_preview: preview::MapPreview,
+    position_unit: basic::PositionUnit,
+    flag_window: flags::FlagWindow,
+    collectible_window: collectibles::CollectibleWindow,
+    boss_window: bosses::BossWindow,
+    script_viewer: script::ScriptViewer,
+    music_preview: music::MusicPreview,
+    icons: icons::Icons,
 }
 
+/// Key of the game data directory in eframe's storage.
+#[cfg(not(target_arch = "wasm32"))]
+const DATA_DIR_KEY: &str = "game_data";
+
 impl MainApp {
-    pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
-        Default::default()
+    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        let app = Self::default();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(dir) = cc.storage.and_then(|s| s.get_string(DATA_DIR_KEY)) {
+            app.data_dirs.load(dir.into());
+        }
+        #[cfg(target_arch = "wasm32")]
+        let _ = cc;
+
+        app
     }
 }
 
@@ -31,7 +73,11 @@ impl MainApp {
             if ui.button("Save").clicked() {
                 let mut modified_profile = profile.0.clone();
                 profile.1.write(&mut modified_profile);
-                let bytes: Vec<u8> = modified_profile.into();
+                let mut bytes: Vec<u8> = modified_profile.into();
+                self.equip_bits.apply(&mut bytes);
+                self.stars.apply(&mut bytes);
+                self.unknown.apply(&mut bytes);
+                self.flags.apply(&mut bytes);
                 self.storage.try_write_data(&bytes);
             }
         }
@@ -41,6 +87,9 @@ impl MainApp {
         if ui.button("Open").clicked() {
             self.storage.open_dialog();
         }
+        self.nikumaru.file_ops(ui);
+        self.config.file_ops(ui);
+        self.settings.file_ops(ui);
         if let Some((_, gameprofile)) = &mut self.profile {
             if ui.button("Enable all teleporters").clicked() {
                 MainApp::enable_all_teleporters(gameprofile);
@@ -74,35 +123,150 @@ impl MainApp {
         else {
             return;
         };
+        let names = self.data_dirs.names();
 
+        let builtin_pickups;
+        let pickups = match self.data_dirs.current() {
+            Some(data) if !data.collectibles.is_empty() => &data.collectibles,
+            _ => {
+                builtin_pickups = collectibles::builtin_collectibles();
+                &builtin_pickups
+            }
+        };
         egui::Window::new("Basic").show(ctx, |ui| {
-            basic::draw_window(ui, health, max_health, music, map, position);
+            basic::draw_window(ui, health, max_health, music, map, &mut self.unknown, names);
+            collectibles::draw_health_sync(ui, health, max_health, &mut self.flags, pickups);
+            let song = self.unknown.music.unwrap_or(music.id());
+            self.music_preview.draw(ui, self.data_dirs.current(), song);
+            let map_id = self.unknown.map.unwrap_or(map.id());
+            // map sizes are read from the stages of a data folder
+            let map_size = self
+                .data_dirs
+                .current()
+                .and_then(|data| data.map_size(map_id));
+            basic::draw_position(ui, position, &mut self.position_unit, map_size);
+            warp::draw_picker(
+                ui,
+                self.data_dirs.current(),
+                map,
+                music,
+                position,
+                &mut self.unknown,
+            );
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.map_preview.open, "Map preview");
+                ui.checkbox(&mut self.script_viewer.open, "Map script");
+                ui.checkbox(&mut self.flag_window.open, "Flags");
+                ui.checkbox(&mut self.collectible_window.open, "Collectibles");
+                ui.checkbox(&mut self.boss_window.open, "Bosses");
+            });
         });
 
-        egui::Window::new("Equipments").show(ctx, |ui| {
-            for (i, equip) in Equipment::iter().enumerate() {
-                ui.checkbox(&mut self.equip_checked[i], equip.to_string());
-                equipment.switch(equip, self.equip_checked[i]);
+        let map_id = self.unknown.map.unwrap_or(map.id());
+        if self.map_preview.open {
+            self.map_preview
+                .show(ctx, self.data_dirs.current(), map_id, position);
+        }
+        if self.script_viewer.open {
+            if let Some(flag) =
+                self.script_viewer
+                    .show(ctx, self.data_dirs.current(), map_id, &self.flags)
+            {
+                self.flag_window.jump(flag);
             }
-        });
+        }
+        if self.flag_window.open {
+            let index = self.data_dirs.current().map(|data| &data.flags);
+            self.flag_window.show(ctx, &mut self.flags, index);
+        }
+        if self.boss_window.open {
+            let builtin;
+            let fights = match self.data_dirs.current() {
+                Some(data) if !data.boss_fights.is_empty() => &data.boss_fights,
+                _ => {
+                    builtin = bosses::builtin_fights();
+                    &builtin
+                }
+            };
+            self.boss_window.show(ctx, &mut self.flags, fights, map_id);
+        }
 
-        egui::Window::new("Weapons").show(ctx, |ui| {
-            weapon::draw_window(ui, &mut self.weapon_num, weapon);
+        egui::Window::new("Equipments").show(ctx, |ui| {
+            let machine_gun =
+                equipment::has_machine_gun(&weapon[..self.weapon_num], &self.unknown.weapon);
+            equipment::draw_window(
+                ui,
+                equipment,
+                &mut self.equip_bits,
+                &mut self.stars,
+                machine_gun,
+            );
         });
 
+        let builtin;
+        let trades = match self.data_dirs.current() {
+            Some(data) if !data.trades.is_empty() => &data.trades,
+            _ => {
+                builtin = trade::builtin_trades();
+                &builtin
+            }
+        };
+        let traded = egui::Window::new("Weapons")
+            .show(ctx, |ui| {
+                weapon::draw_window(
+                    ui,
+                    &mut self.weapon_num,
+                    weapon,
+                    &mut self.unknown.weapon,
+                    names,
+                    self.icons.weapons.as_ref(),
+                    trades,
+                )
+            })
+            .and_then(|response| response.inner.flatten())
+            .map(|(slot, i)| (slot, trades[i].clone()));
+
         egui::Window::new("Inventory").show(ctx, |ui| {
-            inventory::draw_window(ui, &mut self.inventory_num, inventory);
+            inventory::draw_window(
+                ui,
+                &mut self.inventory_num,
+                inventory,
+                &mut self.unknown.inventory,
+                names,
+                self.icons.items.as_ref(),
+            );
         });
+
+        if let Some((slot, trade)) = traded {
+            self.apply_trade(slot, &trade);
+        }
+        if self.collectible_window.open {
+            self.show_collectibles(ctx);
+        }
     }
 }
 
 impl eframe::App for MainApp {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        let dir = self.data_dirs.current_dir();
+        storage.set_string(
+            DATA_DIR_KEY,
+            dir.map(|d| d.display().to_string()).unwrap_or_default(),
+        );
+    }
+
     fn ui(&mut self, ui: &mut egui::Ui, _frame: &mut eframe::Frame) {
         if let Some(data) = self.storage.try_read_data() {
             let _ = self.verify_and_init(data);
         }
 
         self.storage.drag_handle(ui.ctx());
+        self.data_dirs.poll();
+        self.icons.update(ui.ctx(), self.data_dirs.current());
+        self.nikumaru.update(ui.ctx());
+        self.config.update(ui.ctx());
+        self.settings.update(ui.ctx());
 
         Panel::top("top_panel").show_inside(ui, |ui| {
             egui::MenuBar::new().ui(ui, |ui| {
@@ -110,15 +274,18 @@ impl eframe::App for MainApp {
                     ui.set_width(80.0);
                     self.file_ops(ui);
                 });
+                ui.menu_button("Data", |ui| {
+                    self.data_dirs.menu(ui);
+                });
             });
         });
 
         egui::CentralPanel::default().show_inside(ui, |ui| {
             ui.horizontal(|ui| {
-                if let Some(profile) = &mut self.profile {
+                if let Some(profile) = &self.profile {
                     if ui.button("Undo all").clicked() {
-                        profile.1 = GameProfile::dump(&profile.0);
-                        self.update_state();
+                        let data = profile.0.clone().into();
+                        let _ = self.verify_and_init(data);
                     }
                 }
                 self.show_save_button(ui);
@@ -130,6 +297,9 @@ impl eframe::App for MainApp {
             } else {
                 self.draw_editor(ui.ctx());
             }
+            self.nikumaru.show(ui.ctx());
+            self.config.show(ui.ctx());
+            self.settings.show(ui.ctx());
 
             Panel::bottom("bottom-about").show_inside(ui, |ui| {
                 if cfg!(target_arch = "wasm32") {
@@ -158,7 +328,11 @@ impl eframe::App for MainApp {
 }
 
 mod basic;
+mod bosses;
+mod collectibles;
+mod equipment;
 mod inventory;
 mod weapon;
 
 mod utils;
+mod widgets;
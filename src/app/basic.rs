@@ -1,16 +1,19 @@
-use cavestory_save::{
-    items::{Map, Position, Song},
-    strum::IntoEnumIterator,
-};
+use cavestory_save::items::{Map, Position, Song};
 use egui::{DragValue, Ui};
 
+use super::data::NameTable;
+use super::preview::{PIXEL_UNITS, TILE_SIZE};
+use super::utils::raw::UnknownIds;
+use super::widgets::{id_combo, id_label, id_options};
+
 pub fn draw_window(
     ui: &mut Ui,
     health: &mut i16,
     max_health: &mut i16,
     music: &mut Song,
     map: &mut Map,
-    position: &mut Position,
+    unknown: &mut UnknownIds,
+    names: Option<&NameTable>,
 ) {
     ui.horizontal(|ui| {
         ui.label("heal");
@@ -22,30 +25,134 @@ pub fn draw_window(
     });
 
     ui.label("BGM");
-    egui::ComboBox::new("background_music", "")
-        .selected_text(music.to_string())
-        .width(200.)
-        .show_ui(ui, |ui| {
-            for bg_music in Song::iter() {
-                ui.selectable_value(music, bg_music, bg_music.to_string());
-            }
-        });
+    ui.horizontal(|ui| {
+        id_combo(
+            ui,
+            "background_music",
+            id_label(*music, unknown.music, names),
+            200.,
+            music,
+            &mut unknown.music,
+            &id_options::<Song>(ui.ctx(), names, false),
+        );
+    });
 
     ui.label("Map");
-    egui::ComboBox::new("map", "")
-        .selected_text(map.to_string())
-        .width(200.)
-        .show_ui(ui, |ui| {
-            for map_option in Map::iter() {
-                ui.selectable_value(map, map_option, map_option.to_string());
-            }
-        });
-
-    ui.label("Position");
     ui.horizontal(|ui| {
-        ui.label("x: ");
-        ui.add(DragValue::new(&mut position.x));
-        ui.label("y: ");
-        ui.add(DragValue::new(&mut position.y));
+        id_combo(
+            ui,
+            "map",
+            id_label(*map, unknown.map, names),
+            200.,
+            map,
+            &mut unknown.map,
+            &id_options::<Map>(ui.ctx(), names, false),
+        );
+    });
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum PositionUnit {
+    Tile,
+    Pixel,
+    /// The stored value, half a pixel each.
+    #[default]
+    Raw,
+}
+
+impl PositionUnit {
+    const ALL: [Self; 3] = [Self::Tile, Self::Pixel, Self::Raw];
+
+    /// Stored units per unit.
+    fn scale(self) -> i32 {
+        match self {
+            PositionUnit::Tile => TILE_SIZE * PIXEL_UNITS,
+            PositionUnit::Pixel => PIXEL_UNITS,
+            PositionUnit::Raw => 1,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            PositionUnit::Tile => "tiles",
+            PositionUnit::Pixel => "pixels",
+            PositionUnit::Raw => "half pixels",
+        }
+    }
+}
+
+/// The center of the tile containing `value`, clamped to what a position can hold.
+fn snap(value: i16) -> i16 {
+    let tile = TILE_SIZE * PIXEL_UNITS;
+    let center = i32::from(value).div_euclid(tile) * tile + tile / 2;
+    clamp(center)
+}
+
+fn clamp(value: i32) -> i16 {
+    value.clamp(i16::MIN.into(), i16::MAX.into()) as i16
+}
+
+fn coordinate(ui: &mut Ui, label: &str, value: &mut i16, unit: PositionUnit) {
+    ui.label(label);
+    let scale = unit.scale();
+    if unit == PositionUnit::Raw {
+        ui.add(DragValue::new(value));
+    } else {
+        let mut scaled = f64::from(*value) / f64::from(scale);
+        let speed = if unit == PositionUnit::Tile { 0.1 } else { 1. };
+        let range = f64::from(i16::MIN) / f64::from(scale)..=f64::from(i16::MAX) / f64::from(scale);
+        if ui
+            .add(
+                DragValue::new(&mut scaled)
+                    .speed(speed)
+                    .max_decimals(3)
+                    .range(range),
+            )
+            .changed()
+        {
+            *value = clamp((scaled * f64::from(scale)).round() as i32);
+        }
+    }
+}
+
+/// Position editor; `map_size` is the size of the current map in tiles, when known.
+pub fn draw_position(
+    ui: &mut Ui,
+    position: &mut Position,
+    unit: &mut PositionUnit,
+    map_size: Option<[usize; 2]>,
+) {
+    ui.horizontal(|ui| {
+        ui.label("Position");
+        for option in PositionUnit::ALL {
+            ui.selectable_value(unit, option, option.name());
+        }
     });
+    ui.horizontal(|ui| {
+        coordinate(ui, "x: ", &mut position.x, *unit);
+        coordinate(ui, "y: ", &mut position.y, *unit);
+        if ui
+            .button("snap")
+            .on_hover_text("move to the center of the tile")
+            .clicked()
+        {
+            position.x = snap(position.x);
+            position.y = snap(position.y);
+        }
+    });
+
+    if let Some([width, height]) = map_size {
+        let tile = TILE_SIZE * PIXEL_UNITS;
+        let (max_x, max_y) = (width as i32 * tile, height as i32 * tile);
+        ui.label(format!("map size: {width} x {height} tiles"));
+        let (x, y) = (i32::from(position.x), i32::from(position.y));
+        if !(0..max_x).contains(&x) || !(0..max_y).contains(&y) {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                "⚠ the position is outside of the map",
+            );
+        }
+    } else {
+        ui.weak("map size unknown, the stages of a data folder give it");
+    }
 }
@@ -0,0 +1,140 @@
+use egui::{Context, ScrollArea, Ui};
+
+use super::data::{vanilla, xref::BossFight};
+use super::utils::raw::Flags;
+
+#[derive(Default)]
+pub struct BossWindow {
+    pub open: bool,
+}
+
+/// Boss fights of the vanilla game, used without a data folder.
+pub fn builtin_fights() -> Vec<BossFight> {
+    vanilla::BOSSES
+        .iter()
+        .map(|&(map, flag, name)| BossFight {
+            name: Some(name),
+            script: vanilla::map_name(map).unwrap_or_default().to_owned(),
+            map: Some(map),
+            events: None,
+            flag,
+            story_flags: Vec::new(),
+            next_maps: Vec::new(),
+        })
+        .collect()
+}
+
+/// The boss's name if known, and where the fight is.
+fn label(fight: &BossFight) -> String {
+    let place = match fight.events {
+        Some((event, _)) => format!("{} #{event:04}", fight.script),
+        None => fight.script.clone(),
+    };
+    match fight.name {
+        Some(name) => format!("{name}: {place}"),
+        None => place,
+    }
+}
+
+fn hover(fight: &BossFight) -> String {
+    match fight.events {
+        Some((_, death)) => format!(
+            "dies in #{death:04}, whose first flag {:04} is taken as defeated",
+            fight.flag
+        ),
+        None => format!("defeated flag {:04}", fight.flag),
+    }
+}
+
+fn flag_list(flags: &[u16]) -> String {
+    flags
+        .iter()
+        .map(|f| format!("{f:04}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Warn when the flags the death event sets disagree with the defeated flag, offering to fix them.
+fn draw_story_check(ui: &mut Ui, flags: &mut Flags, fight: &BossFight, defeated: bool) {
+    let mismatched: Vec<u16> = fight
+        .story_flags
+        .iter()
+        .copied()
+        .filter(|&f| flags.get(f.into()) != defeated)
+        .collect();
+    if mismatched.is_empty() {
+        return;
+    }
+    let (warning, fix) = if defeated {
+        (
+            "defeated, but the fight's later flags aren't set",
+            "Set them",
+        )
+    } else {
+        (
+            "not defeated, but the fight's later flags are set",
+            "Clear them",
+        )
+    };
+    ui.horizontal(|ui| {
+        ui.colored_label(ui.visuals().warn_fg_color, format!("⚠ {warning}"))
+            .on_hover_text(flag_list(&mismatched));
+        if ui.small_button(fix).clicked() {
+            for flag in mismatched {
+                flags.set(flag.into(), defeated);
+            }
+        }
+    });
+}
+
+/// Warn when the player is on a map the boss's death leads to, yet the boss isn't defeated.
+fn draw_map_check(ui: &mut Ui, flags: &mut Flags, fight: &BossFight, current_map: i32) {
+    if flags.get(fight.flag.into()) || !fight.next_maps.contains(&current_map) {
+        return;
+    }
+    ui.horizontal(|ui| {
+        ui.colored_label(
+            ui.visuals().warn_fg_color,
+            "⚠ not defeated, but the current map comes after the fight",
+        )
+        .on_hover_text(format!("its death event leads to map {current_map}"));
+        if ui.small_button("Set defeated").clicked() {
+            flags.set(fight.flag.into(), true);
+        }
+    });
+}
+
+impl BossWindow {
+    pub fn show(
+        &mut self,
+        ctx: &Context,
+        flags: &mut Flags,
+        fights: &[BossFight],
+        current_map: i32,
+    ) {
+        egui::Window::new("Bosses")
+            .open(&mut self.open)
+            .default_height(400.)
+            .show(ctx, |ui| {
+                if fights.iter().all(|f| f.events.is_none()) {
+                    ui.weak("vanilla bosses; their events and later flags need a data folder");
+                }
+                ScrollArea::vertical().show(ui, |ui| {
+                    for fight in fights {
+                        let mut defeated = flags.get(fight.flag.into());
+                        ui.horizontal(|ui| {
+                            if ui.checkbox(&mut defeated, "").changed() {
+                                flags.set(fight.flag.into(), defeated);
+                            }
+                            ui.label(label(fight)).on_hover_text(hover(fight));
+                            if fight.map == Some(current_map) {
+                                ui.weak("◀ current map");
+                            }
+                        });
+                        draw_map_check(ui, flags, fight, current_map);
+                        draw_story_check(ui, flags, fight, flags.get(fight.flag.into()));
+                    }
+                });
+            });
+    }
+}
@@ -0,0 +1,561 @@
+use cavestory_save::{
+    items::{Inventory, WeaponType},
+    GameProfile,
+};
+use egui::{Button, Checkbox, Context, ScrollArea, Ui};
+
+use super::data::{
+    vanilla,
+    xref::{Collectible, Pickup},
+    NameTable,
+};
+use super::inventory;
+use super::utils::raw::{Flags, GameId, UnknownIds};
+use super::weapon::MAX_WEAPON_NUM;
+use super::widgets::{name_of, set_id};
+use crate::MainApp;
+
+#[derive(Default)]
+pub struct CollectibleWindow {
+    pub open: bool,
+    /// Hide what's already picked up.
+    missing_only: bool,
+}
+
+const GROUPS: [&str; 3] = ["Life Capsules", "Weapons and missile expansions", "Items"];
+
+fn group(pickup: Pickup) -> usize {
+    match pickup {
+        Pickup::Life(_) => 0,
+        Pickup::Weapon { .. } => 1,
+        Pickup::Item(_) => 2,
+    }
+}
+
+/// Collectibles of the vanilla game, used without a data folder.
+pub fn builtin_collectibles() -> Vec<Collectible> {
+    vanilla::COLLECTIBLES
+        .iter()
+        .map(|&(map, flag, pickup)| Collectible {
+            script: vanilla::map_name(map).unwrap_or_default().to_owned(),
+            event: None,
+            flag,
+            pickups: vec![pickup],
+        })
+        .collect()
+}
+
+fn describe(pickup: Pickup, names: Option<&NameTable>) -> String {
+    match pickup {
+        Pickup::Life(0) => "Life Capsule".to_owned(),
+        Pickup::Life(life) => format!("Life Capsule +{life}"),
+        Pickup::Weapon { weapon, ammo: 0 } => name_of::<WeaponType>(weapon, names),
+        Pickup::Weapon { weapon, ammo } => {
+            format!("{} +{ammo} ammo", name_of::<WeaponType>(weapon, names))
+        }
+        Pickup::Item(item) => name_of::<Inventory>(item, names),
+    }
+}
+
+/// Max health before any Life Capsule.
+const BASE_MAX_HEALTH: i16 = 3;
+
+/// What a Life Capsule adds, if its amount is known.
+fn life(collectible: &Collectible) -> Option<i16> {
+    match collectible.pickup() {
+        Pickup::Life(life) => i16::try_from(life).ok().filter(|&life| life > 0),
+        _ => None,
+    }
+}
+
+/// Max health the set Life Capsule flags add up to.
+pub fn expected_max_health(flags: &Flags, collectibles: &[Collectible]) -> i16 {
+    collectibles
+        .iter()
+        .filter(|c| flags.get(c.flag.into()))
+        .filter_map(life)
+        .fold(BASE_MAX_HEALTH, i16::saturating_add)
+}
+
+/// The Life Capsules, as indices into `collectibles`, adding up to exactly `max_health`.
+fn capsules_for(max_health: i16, collectibles: &[Collectible]) -> Option<Vec<usize>> {
+    let target = usize::try_from(max_health.saturating_sub(BASE_MAX_HEALTH)).ok()?;
+    // subset sum: for each reachable sum, the last capsule reaching it
+    let mut reachable = vec![false; target + 1];
+    let mut via = vec![None; target + 1];
+    reachable[0] = true;
+    for (i, collectible) in collectibles.iter().enumerate() {
+        let Some(life) = life(collectible)
+            .and_then(|life| usize::try_from(life).ok())
+            .filter(|&life| life > 0)
+        else {
+            continue;
+        };
+        for sum in (life..=target).rev() {
+            if reachable[sum - life] && !reachable[sum] {
+                reachable[sum] = true;
+                via[sum] = Some((i, life));
+            }
+        }
+    }
+    if !reachable[target] {
+        return None;
+    }
+    let mut picked = Vec::new();
+    let mut sum = target;
+    while let Some((i, life)) = via[sum] {
+        picked.push(i);
+        sum -= life;
+    }
+    Some(picked)
+}
+
+/// Set exactly the Life Capsule flags adding up to `max_health`.
+/// Returns false, leaving the flags as they are, if no set of capsules does.
+fn sync_flags(max_health: i16, flags: &mut Flags, collectibles: &[Collectible]) -> bool {
+    let Some(picked) = capsules_for(max_health, collectibles) else {
+        return false;
+    };
+    for (i, collectible) in collectibles.iter().enumerate() {
+        if life(collectible).is_some() {
+            flags.set(collectible.flag.into(), picked.contains(&i));
+        }
+    }
+    true
+}
+
+/// Compare max health with the Life Capsules picked up, offering to sync either way.
+pub fn draw_health_sync(
+    ui: &mut Ui,
+    health: &mut i16,
+    max_health: &mut i16,
+    flags: &mut Flags,
+    collectibles: &[Collectible],
+) {
+    if !collectibles.iter().any(|c| life(c).is_some()) {
+        // the vanilla table knows where the capsules are, but not what they add
+        let capsules: Vec<_> = collectibles
+            .iter()
+            .filter(|c| matches!(c.pickup(), Pickup::Life(_)))
+            .collect();
+        let picked = capsules.iter().filter(|c| flags.get(c.flag.into())).count();
+        ui.weak(format!(
+            "{picked}/{} Life Capsules picked up; their amounts need a data folder",
+            capsules.len()
+        ));
+        return;
+    }
+
+    let expected = expected_max_health(flags, collectibles);
+    if expected == *max_health {
+        ui.label(format!(
+            "✔ matches the Life Capsules picked up ({expected})"
+        ));
+        return;
+    }
+    ui.label(format!("Life Capsules picked up give {expected}"));
+    ui.horizontal(|ui| {
+        if ui.button("Max health ← capsules").clicked() {
+            *max_health = expected;
+            *health = (*health).min(expected);
+        }
+        let fits = capsules_for(*max_health, collectibles).is_some();
+        if ui
+            .add_enabled(fits, Button::new("Capsules ← max health"))
+            .on_hover_text("sets the flags of the capsules adding up to max health")
+            .on_disabled_hover_text(format!("no set of capsules adds up to {max_health}"))
+            .clicked()
+        {
+            sync_flags(*max_health, flags, collectibles);
+        }
+    });
+}
+
+/// Whether the ammo of `a` goes to `b`: the Super Missile Launcher replaces the
+/// Missile Launcher, and keeps getting its expansions.
+fn same_ammo(a: i32, b: i32) -> bool {
+    const MISSILES: [WeaponType; 2] = [WeaponType::MissileLauncher, WeaponType::SuperMissile];
+    a == b || MISSILES.iter().all(|m| [a, b].contains(&m.id()))
+}
+
+/// Ids of the used weapon slots.
+fn weapon_ids<'a>(
+    profile: &'a GameProfile,
+    unknown: &'a UnknownIds,
+    weapon_num: usize,
+) -> impl Iterator<Item = i32> + 'a {
+    profile.weapon[..weapon_num]
+        .iter()
+        .zip(&unknown.weapon)
+        .map(|(w, unknown)| unknown.unwrap_or(w.classification.id()))
+}
+
+/// The used weapon slot holding `weapon`, or the launcher sharing its ammo, if any.
+fn held(
+    profile: &GameProfile,
+    unknown: &UnknownIds,
+    weapon_num: usize,
+    weapon: i32,
+) -> Option<usize> {
+    let ids: Vec<i32> = weapon_ids(profile, unknown, weapon_num).collect();
+    ids.iter()
+        .position(|&id| id == weapon)
+        .or_else(|| ids.iter().position(|&id| same_ammo(id, weapon)))
+}
+
+/// The pickup of the branch which would run: the one for a weapon held, or the first.
+fn branch(
+    collectible: &Collectible,
+    profile: &GameProfile,
+    unknown: &UnknownIds,
+    weapon_num: usize,
+) -> Pickup {
+    let held_exactly = |pickup: &&Pickup| match pickup {
+        Pickup::Weapon { weapon, .. } => {
+            weapon_ids(profile, unknown, weapon_num).any(|id| id == *weapon)
+        }
+        _ => false,
+    };
+    collectible
+        .pickups
+        .iter()
+        .find(held_exactly)
+        .copied()
+        .unwrap_or(collectible.pickup())
+}
+
+/// Whether a collectible can be toggled: a weapon not held yet needs a free slot.
+fn can_toggle(
+    pickup: Pickup,
+    profile: &GameProfile,
+    unknown: &UnknownIds,
+    weapon_num: usize,
+) -> bool {
+    match pickup {
+        Pickup::Weapon { weapon, .. } => {
+            weapon_num < MAX_WEAPON_NUM || held(profile, unknown, weapon_num, weapon).is_some()
+        }
+        Pickup::Life(_) | Pickup::Item(_) => true,
+    }
+}
+
+/// Give what a collectible gives, or take it back.
+fn apply(
+    pickup: Pickup,
+    picked: bool,
+    profile: &mut GameProfile,
+    unknown: &mut UnknownIds,
+    weapon_num: &mut usize,
+    inventory_num: &mut usize,
+) {
+    match pickup {
+        Pickup::Life(life) => {
+            let life = i16::try_from(life).unwrap_or(0);
+            profile.max_health = if picked {
+                profile.max_health.saturating_add(life)
+            } else {
+                profile.max_health.saturating_sub(life)
+            };
+            profile.health = profile.health.min(profile.max_health);
+        }
+        // like `<AM+`, a weapon not held yet is added with the ammo
+        Pickup::Weapon { weapon, ammo } => match held(profile, unknown, *weapon_num, weapon) {
+            Some(at) => {
+                let w = &mut profile.weapon[at];
+                if picked {
+                    w.max_ammo += ammo;
+                    w.ammo += ammo;
+                } else {
+                    w.max_ammo = (w.max_ammo - ammo).max(0);
+                    w.ammo = w.ammo.min(w.max_ammo);
+                }
+            }
+            None if picked && *weapon_num < MAX_WEAPON_NUM => {
+                let at = *weapon_num;
+                let w = &mut profile.weapon[at];
+                set_id(&mut w.classification, &mut unknown.weapon[at], weapon);
+                w.level = 1;
+                w.exp = 0;
+                w.max_ammo = ammo;
+                w.ammo = ammo;
+                *weapon_num += 1;
+            }
+            None => {}
+        },
+        Pickup::Item(item) => {
+            let (inventory, unknown) = (&mut profile.inventory, &mut unknown.inventory);
+            if picked {
+                inventory::give(inventory, unknown, inventory_num, item);
+            } else {
+                inventory::take(inventory, unknown, inventory_num, item);
+            }
+        }
+    }
+}
+
+impl MainApp {
+    /// Every collectible of the data folder, or of the vanilla game without one,
+    /// toggling its flag along with what it gives.
+    pub(super) fn show_collectibles(&mut self, ctx: &Context) {
+        let Some((_, profile)) = &mut self.profile else {
+            return;
+        };
+        let data = self.data_dirs.current();
+        let names = data.map(|data| &data.names);
+        let builtin;
+        let collectibles = match data {
+            Some(data) if !data.collectibles.is_empty() => &data.collectibles,
+            _ => {
+                builtin = builtin_collectibles();
+                &builtin
+            }
+        };
+        let window = &mut self.collectible_window;
+
+        let mut open = window.open;
+        egui::Window::new("Collectibles")
+            .open(&mut open)
+            .default_height(400.)
+            .show(ctx, |ui| {
+                if collectibles.iter().all(|c| c.event.is_none()) {
+                    ui.weak("vanilla pickups; Life Capsule amounts need a data folder");
+                }
+                ui.checkbox(&mut window.missing_only, "missing only");
+                ScrollArea::vertical().show(ui, |ui| {
+                    for (i, title) in GROUPS.into_iter().enumerate() {
+                        let members: Vec<_> = collectibles
+                            .iter()
+                            .filter(|c| group(c.pickup()) == i)
+                            .collect();
+                        let picked = members
+                            .iter()
+                            .filter(|c| self.flags.get(c.flag.into()))
+                            .count();
+                        let header = format!("{title} ({picked}/{})", members.len());
+                        egui::CollapsingHeader::new(header)
+                            .id_salt(title)
+                            .default_open(i < 2)
+                            .show(ui, |ui| {
+                                for c in members {
+                                    let mut picked = self.flags.get(c.flag.into());
+                                    if window.missing_only && picked {
+                                        continue;
+                                    }
+                                    let gives: Vec<_> =
+                                        c.pickups.iter().map(|&p| describe(p, names)).collect();
+                                    let text = format!("{}: {}", c.script, gives.join(" or "));
+                                    let pickup = branch(c, profile, &self.unknown, self.weapon_num);
+                                    let enabled = picked
+                                        || can_toggle(
+                                            pickup,
+                                            profile,
+                                            &self.unknown,
+                                            self.weapon_num,
+                                        );
+                                    let hover = match c.event {
+                                        Some(event) => format!("#{event:04}, flag {:04}", c.flag),
+                                        None => format!("flag {:04}", c.flag),
+                                    };
+                                    if ui
+                                        .add_enabled(enabled, Checkbox::new(&mut picked, text))
+                                        .on_hover_text(hover)
+                                        .on_disabled_hover_text("no free weapon slot")
+                                        .changed()
+                                    {
+                                        self.flags.set(c.flag.into(), picked);
+                                        apply(
+                                            pickup,
+                                            picked,
+                                            profile,
+                                            &mut self.unknown,
+                                            &mut self.weapon_num,
+                                            &mut self.inventory_num,
+                                        );
+                                    }
+                                }
+                            });
+                    }
+                });
+            });
+        window.open = open;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cavestory_save::Profile;
+
+    use super::*;
+
+    fn capsules(lives: &[i32]) -> Vec<Collectible> {
+        lives
+            .iter()
+            .enumerate()
+            .map(|(i, &life)| Collectible {
+                script: "Test".to_owned(),
+                event: Some(200 + i as u16),
+                flag: 100 + i as u16,
+                pickups: vec![Pickup::Life(life)],
+            })
+            .collect()
+    }
+
+    fn set(flags: &Flags, collectibles: &[Collectible]) -> Vec<bool> {
+        collectibles
+            .iter()
+            .map(|c| flags.get(c.flag.into()))
+            .collect()
+    }
+
+    #[test]
+    fn expected_max_health_adds_set_capsules() {
+        let collectibles = capsules(&[3, 4, 5]);
+        let mut flags = Flags::scan(&[]);
+        assert_eq!(expected_max_health(&flags, &collectibles), BASE_MAX_HEALTH);
+        flags.set(100, true);
+        flags.set(102, true);
+        assert_eq!(
+            expected_max_health(&flags, &collectibles),
+            BASE_MAX_HEALTH + 8
+        );
+    }
+
+    #[test]
+    fn expected_max_health_saturates() {
+        let collectibles = capsules(&[i16::MAX.into(), i16::MAX.into()]);
+        let mut flags = Flags::scan(&[]);
+        flags.set(100, true);
+        flags.set(101, true);
+        assert_eq!(expected_max_health(&flags, &collectibles), i16::MAX);
+    }
+
+    #[test]
+    fn sync_flags_finds_exact_set() {
+        // greedy in script order would take 3 and miss 4 + 5
+        let collectibles = capsules(&[3, 4, 5]);
+        let mut flags = Flags::scan(&[]);
+        flags.set(100, true);
+        assert!(sync_flags(BASE_MAX_HEALTH + 9, &mut flags, &collectibles));
+        assert_eq!(set(&flags, &collectibles), [false, true, true]);
+        assert_eq!(
+            expected_max_health(&flags, &collectibles),
+            BASE_MAX_HEALTH + 9
+        );
+
+        assert!(sync_flags(BASE_MAX_HEALTH, &mut flags, &collectibles));
+        assert_eq!(set(&flags, &collectibles), [false; 3]);
+    }
+
+    #[test]
+    fn sync_flags_leaves_flags_without_exact_set() {
+        let collectibles = capsules(&[3, 4, 5]);
+        let mut flags = Flags::scan(&[]);
+        flags.set(101, true);
+        for max_health in [
+            BASE_MAX_HEALTH + 1,
+            BASE_MAX_HEALTH + 13,
+            BASE_MAX_HEALTH - 1,
+            i16::MIN,
+        ] {
+            assert!(!sync_flags(max_health, &mut flags, &collectibles));
+            assert_eq!(set(&flags, &collectibles), [false, true, false]);
+        }
+    }
+
+    #[test]
+    fn builtin_collectibles_are_named_and_distinct() {
+        let collectibles = builtin_collectibles();
+        assert!(collectibles.iter().all(|c| !c.script.is_empty()));
+        for (i, c) in collectibles.iter().enumerate() {
+            assert!(collectibles[..i].iter().all(|other| other.flag != c.flag));
+        }
+        // missile expansions add what the ammo table gives
+        let missiles = WeaponType::MissileLauncher.id();
+        let expansion = Pickup::Weapon {
+            weapon: missiles,
+            ammo: vanilla::ammo(missiles).unwrap().1,
+        };
+        assert!(collectibles.iter().any(|c| c.pickup() == expansion));
+    }
+
+    #[test]
+    fn unknown_amounts_left_alone() {
+        // a vanilla capsule, whose amount is unknown
+        let collectibles = capsules(&[3, 0, 5]);
+        let mut flags = Flags::scan(&[]);
+        flags.set(101, true);
+        assert_eq!(expected_max_health(&flags, &collectibles), BASE_MAX_HEALTH);
+        assert!(sync_flags(BASE_MAX_HEALTH + 5, &mut flags, &collectibles));
+        assert_eq!(set(&flags, &collectibles), [false, true, true]);
+    }
+
+    /// A profile holding `weapons`, each with 10 of 20 ammo.
+    fn holding(weapons: &[WeaponType]) -> (GameProfile, UnknownIds, usize) {
+        let mut data = vec![0; 0x604];
+        data[..8].copy_from_slice(b"Do041220");
+        let mut profile = GameProfile::dump(&Profile::from_raw_without_length_check(data).unwrap());
+        for (slot, &weapon) in profile.weapon.iter_mut().zip(weapons) {
+            slot.classification = weapon;
+            slot.ammo = 10;
+            slot.max_ammo = 20;
+        }
+        (profile, UnknownIds::default(), weapons.len())
+    }
+
+    fn expansion(pickups: &[(WeaponType, i32)]) -> Collectible {
+        Collectible {
+            script: "Test".to_owned(),
+            event: Some(200),
+            flag: 100,
+            pickups: pickups
+                .iter()
+                .map(|&(weapon, ammo)| Pickup::Weapon {
+                    weapon: weapon.id(),
+                    ammo,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn super_missile_holds_missile_expansions() {
+        let (mut profile, mut unknown, mut weapon_num) =
+            holding(&[WeaponType::PolarStar, WeaponType::SuperMissile]);
+        let missile = WeaponType::MissileLauncher.id();
+        assert_eq!(held(&profile, &unknown, weapon_num, missile), Some(1));
+        assert!(held(&profile, &unknown, weapon_num, WeaponType::Fireball.id()).is_none());
+
+        // an expansion with no Super Missile branch goes to the Super Missile Launcher
+        let c = expansion(&[(WeaponType::MissileLauncher, 5)]);
+        let pickup = branch(&c, &profile, &unknown, weapon_num);
+        let mut inventory_num = 0;
+        apply(
+            pickup,
+            true,
+            &mut profile,
+            &mut unknown,
+            &mut weapon_num,
+            &mut inventory_num,
+        );
+        assert_eq!(weapon_num, 2);
+        assert_eq!(
+            (profile.weapon[1].ammo, profile.weapon[1].max_ammo),
+            (15, 25)
+        );
+    }
+
+    #[test]
+    fn branch_of_the_held_launcher() {
+        let c = expansion(&[
+            (WeaponType::MissileLauncher, 5),
+            (WeaponType::SuperMissile, 3),
+        ]);
+        let (profile, unknown, weapon_num) = holding(&[WeaponType::SuperMissile]);
+        assert_eq!(branch(&c, &profile, &unknown, weapon_num), c.pickups[1]);
+        let (profile, unknown, weapon_num) = holding(&[WeaponType::MissileLauncher]);
+        assert_eq!(branch(&c, &profile, &unknown, weapon_num), c.pickups[0]);
+        // neither held: the first branch, adding the launcher
+        let (profile, unknown, weapon_num) = holding(&[]);
+        assert_eq!(branch(&c, &profile, &unknown, weapon_num), c.pickups[0]);
+    }
+}
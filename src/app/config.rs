@@ -0,0 +1,335 @@
+//! `Config.dat` of the freeware game, normally written by `DoConfig.exe`.
+
+use std::fmt;
+
+use egui::{Context, DragValue, Ui};
+
+use super::storage::{Storage, StorageIO, CONFIG};
+use super::utils::load_error;
+
+const CONFIG_SIZE: usize = 0x94;
+const PROOF: &[u8] = b"DOUKUTSU20041206";
+const PROOF_LEN: usize = 0x20;
+const FONT_LEN: usize = 0x40;
+const JOYSTICK_BUTTONS: usize = 8;
+
+const MOVE_MODES: [&str; 2] = ["Arrows", "<>?"];
+const ATTACK_MODES: [&str; 2] = ["Jump=Z Attack=X", "Jump=X Attack=Z"];
+const OK_MODES: [&str; 2] = ["Jump=OK", "Attack=OK"];
+const DISPLAY_MODES: [&str; 5] = [
+    "Full-screen",
+    "Windowed 320x240",
+    "Windowed 640x480",
+    "Full-screen 24-bit",
+    "Full-screen 32-bit",
+];
+/// Actions a joystick button can be bound to, starting from `1`.
+const JOYSTICK_ACTIONS: [&str; 6] = ["Jump", "Attack", "Weapon+", "Weapon-", "Items", "Map"];
+
+#[derive(Debug)]
+pub enum ConfigError {
+    InvalidSize(usize),
+    InvalidProof,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::InvalidSize(size) => {
+                write!(f, "expected {CONFIG_SIZE} bytes, found {size} bytes")
+            }
+            ConfigError::InvalidProof => write!(f, "not a Config.dat"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Every field is kept as stored, so values unknown to the editor survive a save.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Config {
+    proof: [u8; PROOF_LEN],
+    font_name: [u8; FONT_LEN],
+    pub move_button_mode: i32,
+    pub attack_button_mode: i32,
+    pub ok_button_mode: i32,
+    pub display_mode: i32,
+    pub joystick: bool,
+    /// Raw value of `BOOL`, kept if it isn't `0` or `1`
+    joystick_raw: i32,
+    pub joystick_buttons: [i32; JOYSTICK_BUTTONS],
+}
+
+fn read_i32(data: &[u8], offset: usize) -> i32 {
+    i32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+impl Config {
+    pub fn parse(data: &[u8]) -> Result<Self, ConfigError> {
+        if data.len() != CONFIG_SIZE {
+            return Err(ConfigError::InvalidSize(data.len()));
+        }
+        if !data.starts_with(PROOF) {
+            return Err(ConfigError::InvalidProof);
+        }
+
+        let fields = PROOF_LEN + FONT_LEN;
+        let joystick_raw = read_i32(data, fields + 0x10);
+        Ok(Self {
+            proof: data[..PROOF_LEN].try_into().unwrap(),
+            font_name: data[PROOF_LEN..fields].try_into().unwrap(),
+            move_button_mode: read_i32(data, fields),
+            attack_button_mode: read_i32(data, fields + 0x4),
+            ok_button_mode: read_i32(data, fields + 0x8),
+            display_mode: read_i32(data, fields + 0xC),
+            joystick: joystick_raw != 0,
+            joystick_raw,
+            joystick_buttons: std::array::from_fn(|i| read_i32(data, fields + 0x14 + i * 4)),
+        })
+    }
+
+    pub fn write(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(CONFIG_SIZE);
+        data.extend_from_slice(&self.proof);
+        data.extend_from_slice(&self.font_name);
+        let joystick = match (self.joystick, self.joystick_raw) {
+            (true, 0) => 1,
+            (true, raw) => raw,
+            (false, _) => 0,
+        };
+        for value in [
+            self.move_button_mode,
+            self.attack_button_mode,
+            self.ok_button_mode,
+            self.display_mode,
+            joystick,
+        ]
+        .into_iter()
+        .chain(self.joystick_buttons)
+        {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data
+    }
+
+    pub fn font_name(&self) -> String {
+        let end = self
+            .font_name
+            .iter()
+            .position(|&b| b == 0)
+            .unwrap_or(FONT_LEN);
+        String::from_utf8_lossy(&self.font_name[..end]).into_owned()
+    }
+
+    /// Set the font name, truncated to fit with its terminating NUL.
+    pub fn set_font_name(&mut self, name: &str) {
+        let mut end = name.len().min(FONT_LEN - 1);
+        while !name.is_char_boundary(end) {
+            end -= 1;
+        }
+        self.font_name = [0; FONT_LEN];
+        self.font_name[..end].copy_from_slice(&name.as_bytes()[..end]);
+    }
+}
+
+pub struct ConfigEditor {
+    storage: Storage,
+    config: Option<Config>,
+}
+
+impl Default for ConfigEditor {
+    fn default() -> Self {
+        Self {
+            storage: Storage::new(&CONFIG),
+            config: None,
+        }
+    }
+}
+
+impl ConfigEditor {
+    pub fn update(&mut self, ctx: &Context) {
+        self.storage.drag_handle(ctx);
+        if let Some(data) = self.storage.try_read_data() {
+            match Config::parse(&data) {
+                Ok(config) => self.config = Some(config),
+                Err(e) => load_error(e.to_string()),
+            }
+        }
+    }
+
+    pub fn file_ops(&mut self, ui: &mut Ui) {
+        if ui.button("Open Config.dat").clicked() {
+            self.storage.open_dialog();
+        }
+    }
+
+    pub fn show(&mut self, ctx: &Context) {
+        let Some(config) = &mut self.config else {
+            return;
+        };
+        let mut open = true;
+        egui::Window::new("Config").open(&mut open).show(ctx, |ui| {
+            draw_window(ui, config);
+            ui.separator();
+            if ui.button("Save").clicked() {
+                self.storage.try_write_data(&config.write());
+            }
+        });
+        if !open {
+            self.config = None;
+        }
+    }
+}
+
+/// Combo box over `labels`, showing values outside of them as unknown.
+fn mode_combo(ui: &mut Ui, id_salt: &str, value: &mut i32, labels: &[&str], first: i32) {
+    let label = |v: i32| {
+        usize::try_from(v - first)
+            .ok()
+            .and_then(|i| labels.get(i))
+            .map_or_else(|| format!("Unknown #{v}"), |l| l.to_string())
+    };
+    egui::ComboBox::new(id_salt, "")
+        .selected_text(label(*value))
+        .width(160.)
+        .show_ui(ui, |ui| {
+            for (i, text) in labels.iter().enumerate() {
+                ui.selectable_value(value, first + i as i32, *text);
+            }
+        });
+}
+
+fn draw_window(ui: &mut Ui, config: &mut Config) {
+    egui::Grid::new("config-grid").show(ui, |ui| {
+        ui.label("Font");
+        let mut font_name = config.font_name();
+        if ui.text_edit_singleline(&mut font_name).changed() {
+            config.set_font_name(&font_name);
+        }
+        ui.end_row();
+
+        ui.label("Movement");
+        mode_combo(
+            ui,
+            "config-move",
+            &mut config.move_button_mode,
+            &MOVE_MODES,
+            0,
+        );
+        ui.end_row();
+
+        ui.label("Buttons");
+        mode_combo(
+            ui,
+            "config-attack",
+            &mut config.attack_button_mode,
+            &ATTACK_MODES,
+            0,
+        );
+        ui.end_row();
+
+        ui.label("OK button");
+        mode_combo(ui, "config-ok", &mut config.ok_button_mode, &OK_MODES, 0);
+        ui.end_row();
+
+        ui.label("Display");
+        mode_combo(
+            ui,
+            "config-display",
+            &mut config.display_mode,
+            &DISPLAY_MODES,
+            0,
+        );
+        ui.end_row();
+
+        ui.label("Joystick");
+        ui.checkbox(&mut config.joystick, "use joystick");
+        ui.end_row();
+    });
+
+    if config.joystick {
+        ui.separator();
+        egui::Grid::new("config-joystick").show(ui, |ui| {
+            for (i, action) in config.joystick_buttons.iter_mut().enumerate() {
+                ui.label(format!("Button {}", i + 1));
+                mode_combo(
+                    ui,
+                    &format!("config-joystick-{i}"),
+                    action,
+                    &JOYSTICK_ACTIONS,
+                    1,
+                );
+                ui.add(DragValue::new(action).prefix("#"));
+                ui.end_row();
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Vec<u8> {
+        let mut data = vec![0; CONFIG_SIZE];
+        data[..PROOF.len()].copy_from_slice(PROOF);
+        data[PROOF_LEN..PROOF_LEN + 11].copy_from_slice(b"Courier New");
+        let fields = PROOF_LEN + FONT_LEN;
+        for (i, value) in [1i32, 0, 1, 2, 1, 2, 1, 5, 3, 4, 6, 1, 1]
+            .into_iter()
+            .enumerate()
+        {
+            data[fields + i * 4..fields + i * 4 + 4].copy_from_slice(&value.to_le_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn round_trip() {
+        let data = sample();
+        let config = Config::parse(&data).unwrap();
+        assert_eq!(config.font_name(), "Courier New");
+        assert_eq!(config.display_mode, 2);
+        assert!(config.joystick);
+        assert_eq!(config.joystick_buttons, [2, 1, 5, 3, 4, 6, 1, 1]);
+        assert_eq!(config.write(), data);
+    }
+
+    #[test]
+    fn keep_unknown_values() {
+        let mut data = sample();
+        let fields = PROOF_LEN + FONT_LEN;
+        data[fields + 0xC..fields + 0x10].copy_from_slice(&9i32.to_le_bytes());
+        data[fields + 0x10..fields + 0x14].copy_from_slice(&7i32.to_le_bytes());
+        // garbage after the font name's NUL
+        data[PROOF_LEN + 0x30] = 0xAA;
+
+        let config = Config::parse(&data).unwrap();
+        assert_eq!(config.display_mode, 9);
+        assert_eq!(config.write(), data);
+    }
+
+    #[test]
+    fn edit_font_name() {
+        let mut config = Config::parse(&sample()).unwrap();
+        config.set_font_name("MS Gothic");
+        assert_eq!(config.font_name(), "MS Gothic");
+
+        let long = "x".repeat(100);
+        config.set_font_name(&long);
+        assert_eq!(config.font_name().len(), FONT_LEN - 1);
+        assert_eq!(Config::parse(&config.write()).unwrap(), config);
+    }
+
+    #[test]
+    fn reject_invalid() {
+        assert!(matches!(
+            Config::parse(&[0; 4]),
+            Err(ConfigError::InvalidSize(4))
+        ));
+        assert!(matches!(
+            Config::parse(&[0; CONFIG_SIZE]),
+            Err(ConfigError::InvalidProof)
+        ));
+    }
+}
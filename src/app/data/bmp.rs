@@ -0,0 +1,156 @@
+//! Uncompressed Windows bitmaps, as used for the freeware game's graphics.
+
+/// An RGBA image. Black is the game's transparent color, and becomes transparent here.
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub rgba: Vec<u8>,
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+pub fn decode(data: &[u8]) -> Option<Image> {
+    if !data.starts_with(b"BM") {
+        return None;
+    }
+    let pixels_offset = u32_at(data, 0x0A)? as usize;
+    let header_size = u32_at(data, 0x0E)? as usize;
+    let width = u32_at(data, 0x12)? as i32;
+    let height = u32_at(data, 0x16)? as i32;
+    let bpp = u16_at(data, 0x1C)?;
+    let compression = u32_at(data, 0x1E)?;
+    if compression != 0 || width <= 0 || height == 0 {
+        return None;
+    }
+
+    let colors = match u32_at(data, 0x2E)? {
+        0 if bpp <= 8 => 1 << bpp,
+        n => (n as usize).min(256),
+    };
+    let palette_offset = 0x0E + header_size;
+    let palette: Vec<[u8; 3]> = (0..colors)
+        .filter_map(|i| {
+            let bgr = data.get(palette_offset + i * 4..palette_offset + i * 4 + 3)?;
+            Some([bgr[2], bgr[1], bgr[0]])
+        })
+        .collect();
+
+    let width = width as usize;
+    // negative height means rows are stored top-down
+    let bottom_up = height > 0;
+    let height = height.unsigned_abs() as usize;
+    let stride = width.checked_mul(bpp as usize)?.div_ceil(32) * 4;
+    // reject sizes the pixel data can't hold before allocating for them
+    let pixels_end = stride.checked_mul(height)?.checked_add(pixels_offset)?;
+    if pixels_end > data.len() {
+        return None;
+    }
+
+    let mut rgba = Vec::with_capacity(width.checked_mul(height)?.checked_mul(4)?);
+    for y in 0..height {
+        let row = if bottom_up { height - 1 - y } else { y };
+        let start = pixels_offset + row * stride;
+        let row = data.get(start..start + stride)?;
+        for x in 0..width {
+            let rgb = match bpp {
+                1 | 4 | 8 => {
+                    let bit = x * bpp as usize;
+                    let byte = row[bit / 8];
+                    let shift = 8 - bpp as usize - bit % 8;
+                    let index = (byte >> shift) & ((1u16 << bpp) - 1) as u8;
+                    *palette.get(index as usize)?
+                }
+                24 | 32 => {
+                    let px = &row[x * bpp as usize / 8..];
+                    [px[2], px[1], px[0]]
+                }
+                _ => return None,
+            };
+            let alpha = if rgb == [0, 0, 0] { 0 } else { 255 };
+            rgba.extend_from_slice(&[rgb[0], rgb[1], rgb[2], alpha]);
+        }
+    }
+
+    Some(Image {
+        width,
+        height,
+        rgba,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A bitmap with a 40-byte header, `palette` and the given rows, bottom row first.
+    fn bitmap(width: i32, height: i32, bpp: u16, palette: &[[u8; 3]], rows: &[&[u8]]) -> Vec<u8> {
+        let pixels_offset = 0x36 + palette.len() * 4;
+        let mut data = b"BM".to_vec();
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&(pixels_offset as u32).to_le_bytes());
+        data.extend_from_slice(&40u32.to_le_bytes());
+        data.extend_from_slice(&width.to_le_bytes());
+        data.extend_from_slice(&height.to_le_bytes());
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&bpp.to_le_bytes());
+        data.extend_from_slice(&[0; 24]);
+        data[0x2E..0x32].copy_from_slice(&(palette.len() as u32).to_le_bytes());
+        for &[r, g, b] in palette {
+            data.extend_from_slice(&[b, g, r, 0]);
+        }
+        for row in rows {
+            data.extend_from_slice(row);
+        }
+        data
+    }
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const CLEAR: [u8; 4] = [0, 0, 0, 0];
+
+    #[test]
+    fn decode_paletted() {
+        // 4 bits per pixel, rows padded to 4 bytes
+        let data = bitmap(
+            3,
+            2,
+            4,
+            &[[0, 0, 0], [255, 0, 0]],
+            &[&[0x10, 0x10, 0, 0], &[0x01, 0x00, 0, 0]],
+        );
+        let image = decode(&data).unwrap();
+        assert_eq!((image.width, image.height), (3, 2));
+        assert_eq!(image.rgba[..12], [CLEAR, RED, CLEAR].concat());
+        assert_eq!(image.rgba[12..], [RED, CLEAR, RED].concat());
+    }
+
+    #[test]
+    fn decode_top_down_rgb() {
+        let data = bitmap(1, -2, 24, &[], &[&[0, 0, 255, 0], &[0, 0, 0, 0]]);
+        let image = decode(&data).unwrap();
+        assert_eq!(image.rgba, [RED, CLEAR].concat());
+    }
+
+    #[test]
+    fn reject_invalid() {
+        assert!(decode(b"PNG").is_none());
+        // a row short of the declared height
+        let data = bitmap(1, 3, 24, &[], &[&[0, 0, 255, 0], &[0, 0, 255, 0]]);
+        assert!(decode(&data).is_none());
+        // dimensions which would overflow or need gigabytes
+        let data = bitmap(i32::MAX, i32::MAX, 32, &[], &[]);
+        assert!(decode(&data).is_none());
+        let data = bitmap(1, 1, 12, &[], &[&[0; 4]]);
+        assert!(decode(&data).is_none());
+    }
+}
@@ -0,0 +1,70 @@
+//! Files of a data directory, found by name regardless of case and layout.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// Nested deeper than this is not game data.
+const MAX_DEPTH: usize = 4;
+
+#[derive(Default)]
+pub struct FileIndex {
+    /// Lowercase file name to path, the shallowest one winning.
+    files: BTreeMap<String, PathBuf>,
+}
+
+impl FileIndex {
+    pub fn scan(root: &Path) -> Self {
+        let mut index = Self::default();
+        let mut dirs = vec![(root.to_path_buf(), 0)];
+        // breadth first, so `data/` wins over `data/base/` and the like
+        while !dirs.is_empty() {
+            let mut next = Vec::new();
+            for (dir, depth) in dirs {
+                let Ok(entries) = std::fs::read_dir(&dir) else {
+                    continue;
+                };
+                let mut entries: Vec<_> = entries.flatten().map(|e| e.path()).collect();
+                entries.sort();
+                for path in entries {
+                    if path.is_dir() {
+                        if depth < MAX_DEPTH {
+                            next.push((path, depth + 1));
+                        }
+                    } else if let Some(name) = path.file_name() {
+                        let name = name.to_string_lossy().to_lowercase();
+                        index.files.entry(name).or_insert(path);
+                    }
+                }
+            }
+            dirs = next;
+        }
+        index
+    }
+
+    pub fn find(&self, file_name: &str) -> Option<&Path> {
+        self.files
+            .get(&file_name.to_lowercase())
+            .map(PathBuf::as_path)
+    }
+
+    pub fn read(&self, file_name: &str) -> Option<Vec<u8>> {
+        std::fs::read(self.find(file_name)?).ok()
+    }
+
+    /// Lowercase names of the files with the given extension.
+    pub fn with_extension<'a>(&'a self, extension: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.files
+            .keys()
+            .filter(move |name| {
+                name.rsplit_once('.')
+                    .is_some_and(|(_, ext)| ext == extension)
+            })
+            .map(String::as_str)
+    }
+
+    pub fn count(&self, extension: &str) -> usize {
+        self.with_extension(extension).count()
+    }
+}
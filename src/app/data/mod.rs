@@ -0,0 +1,507 @@
+// Copyright (c) 2025 mokurin000
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use cavestory_save::items::{Inventory, Map, Song, WeaponType};
+use kanal::{unbounded, Receiver, Sender};
+
+use super::utils::raw::GameId;
+use super::warp::Spot;
+
+pub mod bmp;
+pub mod index;
+pub mod org;
+pub mod pxe;
+pub mod pxm;
+pub mod stage;
+pub mod tsc;
+pub mod vanilla;
+pub mod xref;
+
+use index::FileIndex;
+
+/// `ArmsItem.tsc` shows event `1000 + id` for weapons and `5000 + id` for items.
+const WEAPON_EVENT_BASE: u16 = 1000;
+const ITEM_EVENT_BASE: u16 = 5000;
+
+/// What a data directory says about a weapon or an item.
+#[derive(Default)]
+pub struct Details {
+    /// The text shown in the inventory, after the name.
+    pub description: String,
+    pub sources: Vec<xref::Source>,
+}
+
+/// Names read from a game or mod data directory.
+#[derive(Default)]
+pub struct NameTable {
+    pub stages: Vec<stage::Stage>,
+    pub weapons: BTreeMap<i32, String>,
+    pub items: BTreeMap<i32, String>,
+    pub weapon_details: BTreeMap<i32, Details>,
+    pub item_details: BTreeMap<i32, Details>,
+    /// Tells apart the tables of each load, to cache what is derived from them.
+    pub serial: u64,
+}
+
+impl NameTable {
+    pub fn load(files: &FileIndex) -> Self {
+        static SERIAL: AtomicU64 = AtomicU64::new(1);
+        let mut names = Self {
+            serial: SERIAL.fetch_add(1, Ordering::Relaxed),
+            ..Self::default()
+        };
+
+        if let Some(stages) = stage::TABLE_FILES.iter().find_map(|file| {
+            let data = files.read(file)?;
+            stage::parse(file, &data)
+        }) {
+            names.stages = stages;
+        }
+
+        if let Some(script) = read_script(files, "ArmsItem.tsc") {
+            for (event, body) in tsc::events(&script) {
+                let text = tsc::text(body);
+                let mut lines = text
+                    .lines()
+                    .map(|l| l.trim().trim_matches('='))
+                    .filter(|l| !l.is_empty());
+                let Some(name) = lines.next().map(str::to_owned) else {
+                    continue;
+                };
+                let description = lines.collect::<Vec<_>>().join("\n");
+                let (id, table, details) = match event {
+                    ITEM_EVENT_BASE..=5999 => (
+                        event - ITEM_EVENT_BASE,
+                        &mut names.items,
+                        &mut names.item_details,
+                    ),
+                    WEAPON_EVENT_BASE..=1999 => (
+                        event - WEAPON_EVENT_BASE,
+                        &mut names.weapons,
+                        &mut names.weapon_details,
+                    ),
+                    _ => continue,
+                };
+                table.insert(id.into(), name);
+                details.entry(id.into()).or_default().description = description;
+            }
+        }
+
+        let (weapon_sources, item_sources) = xref::sources(files, &names);
+        for (sources, details) in [
+            (weapon_sources, &mut names.weapon_details),
+            (item_sources, &mut names.item_details),
+        ] {
+            for (id, sources) in sources {
+                details.entry(id).or_default().sources = sources;
+            }
+        }
+
+        names
+    }
+
+    pub fn map(&self, id: i32) -> Option<&str> {
+        let stage = self.stages.get(usize::try_from(id).ok()?)?;
+        Some(if stage.name.is_empty() {
+            &stage.filename
+        } else {
+            &stage.name
+        })
+    }
+
+    pub fn weapon(&self, id: i32) -> Option<&str> {
+        self.weapons.get(&id).map(String::as_str)
+    }
+
+    pub fn item(&self, id: i32) -> Option<&str> {
+        self.items.get(&id).map(String::as_str)
+    }
+}
+
+/// Ids which may be named by a [`NameTable`].
+pub trait Named: GameId {
+    /// Whether the data directory's list replaces the variants instead of extending them.
+    const REPLACES_BUILTIN: bool = false;
+
+    fn custom_name(names: &NameTable, id: i32) -> Option<&str>;
+    fn custom_ids(names: &NameTable) -> Vec<i32>;
+
+    fn details(_: &NameTable, _: i32) -> Option<&Details> {
+        None
+    }
+
+    /// Name of a vanilla id, known without a data directory.
+    fn builtin_name(_: i32) -> Option<&'static str> {
+        None
+    }
+
+    /// Description of a vanilla id, known without a data directory.
+    fn builtin_description(_: i32) -> Option<&'static str> {
+        None
+    }
+}
+
+impl Named for Map {
+    const REPLACES_BUILTIN: bool = true;
+
+    fn custom_name(names: &NameTable, id: i32) -> Option<&str> {
+        names.map(id)
+    }
+
+    fn custom_ids(names: &NameTable) -> Vec<i32> {
+        (0..names.stages.len() as i32).collect()
+    }
+
+    fn builtin_name(id: i32) -> Option<&'static str> {
+        vanilla::map_name(id)
+    }
+}
+
+impl Named for WeaponType {
+    fn custom_name(names: &NameTable, id: i32) -> Option<&str> {
+        names.weapon(id)
+    }
+
+    fn custom_ids(names: &NameTable) -> Vec<i32> {
+        names.weapons.keys().copied().collect()
+    }
+
+    fn details(names: &NameTable, id: i32) -> Option<&Details> {
+        names.weapon_details.get(&id)
+    }
+
+    fn builtin_description(id: i32) -> Option<&'static str> {
+        vanilla::description(vanilla::WEAPONS, id)
+    }
+}
+
+impl Named for Inventory {
+    fn custom_name(names: &NameTable, id: i32) -> Option<&str> {
+        names.item(id)
+    }
+
+    fn custom_ids(names: &NameTable) -> Vec<i32> {
+        names.items.keys().copied().collect()
+    }
+
+    fn details(names: &NameTable, id: i32) -> Option<&Details> {
+        names.item_details.get(&id)
+    }
+
+    fn builtin_description(id: i32) -> Option<&'static str> {
+        vanilla::description(vanilla::ITEMS, id)
+    }
+}
+
+impl Named for Song {
+    fn custom_name(_: &NameTable, _: i32) -> Option<&str> {
+        None
+    }
+
+    fn custom_ids(_: &NameTable) -> Vec<i32> {
+        Vec::new()
+    }
+}
+
+pub struct StageMap {
+    pub tiles: pxm::TileMap,
+    pub tileset: Option<bmp::Image>,
+    pub attributes: Option<Vec<u8>>,
+}
+
+/// Entity types worth warping to.
+const SPOT_ENTITIES: [(u16, &str); 4] = [
+    (16, "Save point"),
+    (17, "Refill"),
+    (18, "Door"),
+    (22, "Teleporter"),
+];
+
+/// A decrypted TSC script.
+fn read_script(files: &FileIndex, file_name: &str) -> Option<String> {
+    let data = files.read(file_name)?;
+    Some(String::from_utf8_lossy(&tsc::decrypt(&data)).into_owned())
+}
+
+/// Spots of a stage from its entities, with the music its entrance events play.
+fn stage_spots(files: &FileIndex, map: i32, stage: &stage::Stage) -> Vec<Spot> {
+    let Some(entities) = files
+        .read(&format!("{}.pxe", stage.filename))
+        .and_then(|data| pxe::parse(&data))
+    else {
+        return Vec::new();
+    };
+
+    // events 90 to 99 run when entering a map
+    let music = read_script(files, &format!("{}.tsc", stage.filename))
+        .and_then(|script| {
+            tsc::events(&script)
+                .into_iter()
+                .filter(|(event, _)| (90..=99).contains(event))
+                .flat_map(|(_, body)| tsc::commands(body))
+                .find(|(name, _)| *name == "CMU")
+                .and_then(|(_, args)| args.first().copied())
+        })
+        .map(i32::from);
+
+    entities
+        .iter()
+        .filter_map(|e| {
+            let (_, label) = SPOT_ENTITIES.iter().find(|(kind, _)| *kind == e.kind)?;
+            Some(Spot {
+                map,
+                label: format!("{label} at {}, {}", e.x, e.y),
+                x: e.x.into(),
+                y: e.y.into(),
+                music,
+            })
+        })
+        .collect()
+}
+
+/// A game or mod data directory, indexed once.
+pub struct GameData {
+    pub root: PathBuf,
+    pub files: FileIndex,
+    pub names: NameTable,
+    /// Size in tiles of each stage, read from the `.pxm` headers.
+    map_sizes: Vec<Option<[usize; 2]>>,
+    /// Save points, doors and teleporters of every stage.
+    pub spots: Vec<Spot>,
+    pub flags: xref::FlagIndex,
+    /// Weapon trades done by the scripts.
+    pub trades: Vec<xref::Trade>,
+    /// Life Capsules and other one-time pickups.
+    pub collectibles: Vec<xref::Collectible>,
+    pub boss_fights: Vec<xref::BossFight>,
+}
+
+impl GameData {
+    pub fn load(root: PathBuf) -> Self {
+        let files = FileIndex::scan(&root);
+        let names = NameTable::load(&files);
+        let map_sizes = names
+            .stages
+            .iter()
+            .map(|stage| {
+                let path = files.find(&format!("{}.pxm", stage.filename))?;
+                let mut header = [0; 8];
+                File::open(path).ok()?.read_exact(&mut header).ok()?;
+                pxm::TileMap::size(&header)
+            })
+            .collect();
+        let spots = names
+            .stages
+            .iter()
+            .enumerate()
+            .flat_map(|(map, stage)| stage_spots(&files, map as i32, stage))
+            .collect();
+        let flags = xref::FlagIndex::build(&files, &names);
+        let trades = xref::trades(&files, &names);
+        let collectibles = xref::collectibles(&files, &names);
+        let boss_fights = xref::boss_fights(&files, &names);
+        Self {
+            root,
+            files,
+            names,
+            map_sizes,
+            spots,
+            flags,
+            trades,
+            collectibles,
+            boss_fights,
+        }
+    }
+
+    pub fn map_size(&self, map: i32) -> Option<[usize; 2]> {
+        *self.map_sizes.get(usize::try_from(map).ok()?)?
+    }
+
+    pub fn stage(&self, map: i32) -> Option<&stage::Stage> {
+        self.names.stages.get(usize::try_from(map).ok()?)
+    }
+
+    /// Read an image, in a format the editor can decode. Some translations of the
+    /// freeware game ship their bitmaps as `.pbm`, with the same contents.
+    pub fn image(&self, name: &str) -> Option<bmp::Image> {
+        ["bmp", "pbm"]
+            .iter()
+            .find_map(|ext| self.files.read(&format!("{name}.{ext}")))
+            .and_then(|data| bmp::decode(&data))
+    }
+
+    /// The decrypted script of a map.
+    pub fn stage_script(&self, map: i32) -> Option<String> {
+        read_script(&self.files, &format!("{}.tsc", self.stage(map)?.filename))
+    }
+
+    /// The Organya wave table, if the folder has one.
+    pub fn wave_table(&self) -> Option<Vec<u8>> {
+        org::WAVE_TABLE_FILES
+            .iter()
+            .find_map(|file| self.files.read(file))
+            .filter(|waves| waves.len() >= org::WAVE_TABLE_SIZE)
+    }
+
+    /// The `.org` file of a vanilla song.
+    pub fn song(&self, id: i32) -> Option<Vec<u8>> {
+        let name = org::SONG_FILES.get(usize::try_from(id).ok()?)?;
+        self.files.read(&format!("{name}.org"))
+    }
+
+    /// Tile map, tileset and tile attributes of a stage.
+    pub fn stage_map(&self, map: i32) -> Option<StageMap> {
+        let stage = self.stage(map)?;
+        let tiles = pxm::TileMap::parse(&self.files.read(&format!("{}.pxm", stage.filename))?)?;
+        Some(StageMap {
+            tiles,
+            tileset: self.image(&format!("Prt{}", stage.tileset)),
+            attributes: self.files.read(&format!("{}.pxa", stage.tileset)),
+        })
+    }
+
+    pub fn summary(&self, ui: &mut egui::Ui) {
+        ui.label(self.root.display().to_string());
+        ui.label(format!(
+            "{} stages, {} scripts, {} flags used, {} sprites, {} songs",
+            self.names.stages.len(),
+            self.files.count("tsc"),
+            self.flags.flags().count(),
+            ["bmp", "pbm", "png"]
+                .iter()
+                .map(|ext| self.files.count(ext))
+                .sum::<usize>(),
+            self.files.count("org"),
+        ));
+    }
+}
+
+/// Data directories the user opened, indexed once each.
+pub struct DataDirs {
+    current: Option<PathBuf>,
+    cache: HashMap<PathBuf, GameData>,
+    sender: Sender<GameData>,
+    recv: Receiver<GameData>,
+}
+
+impl Default for DataDirs {
+    fn default() -> Self {
+        let (sender, recv) = unbounded();
+        Self {
+            current: None,
+            cache: HashMap::new(),
+            sender,
+            recv,
+        }
+    }
+}
+
+/// Load `dir` on a blocking thread, as walking and parsing it would stall the task executor.
+#[cfg(not(target_arch = "wasm32"))]
+async fn load_blocking(dir: PathBuf) -> Option<GameData> {
+    compio::runtime::spawn_blocking(move || GameData::load(dir))
+        .await
+        .ok()
+}
+
+impl DataDirs {
+    /// The selected directory, or `None` to use the built-in enums.
+    pub fn current(&self) -> Option<&GameData> {
+        self.cache.get(self.current.as_ref()?)
+    }
+
+    pub fn current_dir(&self) -> Option<&Path> {
+        self.current.as_deref()
+    }
+
+    pub fn names(&self) -> Option<&NameTable> {
+        self.current().map(|data| &data.names)
+    }
+
+    /// Receive directories loaded in background, selecting the latest one.
+    pub fn poll(&mut self) {
+        while let Ok(Some(data)) = self.recv.try_recv() {
+            self.current = Some(data.root.clone());
+            self.cache.insert(data.root.clone(), data);
+        }
+    }
+
+    /// Index `dir` in background.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(&self, dir: PathBuf) {
+        let sender = self.sender.clone();
+        let future = async move {
+            if dir.is_dir() {
+                if let Some(data) = load_blocking(dir).await {
+                    let _ = sender.as_async().send(data).await;
+                }
+            }
+        };
+
+        let _ = crate::TASK_SENDER.get().unwrap().send(Box::pin(future));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn open_dialog(&self) {
+        let sender = self.sender.clone();
+        let future = async move {
+            if let Some(handle) = rfd::AsyncFileDialog::default()
+                .set_title("Pick the game or mod data folder")
+                .pick_folder()
+                .await
+            {
+                // the game folder works as well, files are found in any subfolder
+                let dir = handle.path().to_path_buf();
+                if let Some(data) = load_blocking(dir).await {
+                    let _ = sender.as_async().send(data).await;
+                }
+            }
+        };
+
+        let _ = crate::TASK_SENDER.get().unwrap().send(Box::pin(future));
+    }
+
+    /// The "Data" menu: pick a directory, or switch between opened ones.
+    pub fn menu(&mut self, ui: &mut egui::Ui) {
+        #[cfg(not(target_arch = "wasm32"))]
+        if ui.button("Choose data folder").clicked() {
+            self.open_dialog();
+        }
+        #[cfg(target_arch = "wasm32")]
+        ui.label("Game data is only available in native builds.");
+
+        if let Some(data) = self.current() {
+            ui.separator();
+            data.summary(ui);
+        }
+        ui.separator();
+
+        if ui
+            .selectable_label(self.current.is_none(), "Vanilla")
+            .clicked()
+        {
+            self.current = None;
+        }
+        let mut dirs: Vec<_> = self.cache.keys().cloned().collect();
+        dirs.sort();
+        for dir in dirs {
+            let selected = self.current.as_ref() == Some(&dir);
+            if ui
+                .selectable_label(selected, dir.display().to_string())
+                .clicked()
+            {
+                self.current = Some(dir);
+            }
+        }
+    }
+}
@@ -0,0 +1,473 @@
+//! Organya songs (`.org`), and a software synthesizer to preview them.
+//!
+//! Melody tracks play the game's wave table the way Organya does. Drums are
+//! sound effects in the game, which are approximated here with decaying noise.
+
+use std::fmt::Display;
+
+pub const SAMPLE_RATE: u32 = 44100;
+/// 100 waveforms of 256 signed samples.
+pub const WAVE_TABLE_SIZE: usize = 100 * 256;
+/// Where game data folders keep the wave table: CSE2, then doukutsu-rs.
+pub const WAVE_TABLE_FILES: [&str; 2] = ["Wave.dat", "organya-wavetable-doukutsu.bin"];
+
+/// File names of the vanilla songs, by song id.
+pub const SONG_FILES: [&str; 42] = [
+    "xxxx", "wanpaku", "anzen", "gameover", "gravity", "weed", "mdown2", "fireeye", "vivi", "mura",
+    "fanfale1", "ginsuke", "cemetery", "plant", "kodou", "fanfale3", "fanfale2", "dr", "escape",
+    "jenka", "maze", "access", "ironh", "grand", "curly", "oside", "requiem", "wanpak2", "quiet",
+    "lastcave", "balcony", "lastbtl", "lastbt3", "ending", "zonbie", "bdown", "hell", "jenka2",
+    "marine", "ballos", "toroko", "white",
+];
+
+const TRACKS: usize = 16;
+const MELODY_TRACKS: usize = 8;
+/// Songs are cut after ten minutes, whatever their loops.
+const MAX_SECONDS: usize = 600;
+/// Value of a note field that keeps the previous one.
+const UNCHANGED: u8 = 0xFF;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum OrgError {
+    BadMagic,
+    Truncated,
+}
+
+impl Display for OrgError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrgError::BadMagic => write!(f, "not an Organya song"),
+            OrgError::Truncated => write!(f, "the song is truncated"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Note {
+    /// Start, in ticks.
+    pub position: i32,
+    pub key: u8,
+    /// Length, in ticks.
+    pub length: u8,
+    pub volume: u8,
+    pub pan: u8,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Track {
+    /// Fine tuning, 1000 is in tune.
+    pub freq: u16,
+    /// Waveform of a melody track, or sample of a drum track.
+    pub wave: u8,
+    /// Notes stop by themselves instead of lasting their whole length.
+    pub pizzicato: bool,
+    pub notes: Vec<Note>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Organya {
+    /// Milliseconds per tick.
+    pub wait: u16,
+    /// Ticks where the song loops back to, and where it does.
+    pub repeat: (i32, i32),
+    pub tracks: Vec<Track>,
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], OrgError> {
+        let (bytes, rest) = self.0.split_at_checked(N).ok_or(OrgError::Truncated)?;
+        self.0 = rest;
+        Ok(bytes.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> Result<u8, OrgError> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, OrgError> {
+        Ok(u16::from_le_bytes(self.take()?))
+    }
+
+    fn i32(&mut self) -> Result<i32, OrgError> {
+        Ok(i32::from_le_bytes(self.take()?))
+    }
+}
+
+pub fn parse(data: &[u8]) -> Result<Organya, OrgError> {
+    let mut reader = Reader(data);
+    if !matches!(&reader.take::<6>()?, b"Org-01" | b"Org-02" | b"Org-03") {
+        return Err(OrgError::BadMagic);
+    }
+    let wait = reader.u16()?;
+    // beats per measure and steps per beat only matter to the editor
+    reader.take::<2>()?;
+    let repeat = (reader.i32()?, reader.i32()?);
+
+    let mut headers = Vec::with_capacity(TRACKS);
+    for _ in 0..TRACKS {
+        headers.push((reader.u16()?, reader.u8()?, reader.u8()?, reader.u16()?));
+    }
+
+    let mut tracks = Vec::with_capacity(TRACKS);
+    for (freq, wave, pizzicato, count) in headers {
+        let mut notes = vec![Note::default(); count.into()];
+        for note in &mut notes {
+            note.position = reader.i32()?;
+        }
+        let fields: [fn(&mut Note) -> &mut u8; 4] = [
+            |n| &mut n.key,
+            |n| &mut n.length,
+            |n| &mut n.volume,
+            |n| &mut n.pan,
+        ];
+        for field in fields {
+            for note in &mut notes {
+                *field(note) = reader.u8()?;
+            }
+        }
+        tracks.push(Track {
+            freq,
+            wave,
+            pizzicato: pizzicato != 0,
+            notes,
+        });
+    }
+
+    Ok(Organya {
+        wait,
+        repeat,
+        tracks,
+    })
+}
+
+/// Frequencies of the notes of an octave, as Organya tunes them.
+const FREQUENCIES: [u32; 12] = [261, 278, 294, 311, 329, 349, 371, 391, 414, 440, 466, 494];
+/// Per octave: samples in a period, frequency multiplier, and periods of a pizzicato note.
+const OCTAVES: [(usize, u32, usize); 8] = [
+    (256, 1, 4),
+    (256, 2, 8),
+    (128, 4, 12),
+    (128, 8, 16),
+    (64, 16, 20),
+    (32, 32, 24),
+    (16, 64, 28),
+    (8, 128, 32),
+];
+
+struct Voice {
+    samples: Vec<f32>,
+    looped: bool,
+    /// Samples of `samples` per output sample.
+    step: f64,
+    phase: f64,
+    /// Output samples left, for melody notes.
+    remaining: Option<usize>,
+}
+
+impl Voice {
+    fn next(&mut self) -> Option<f32> {
+        if self.remaining == Some(0) {
+            return None;
+        }
+        let mut index = self.phase as usize;
+        if self.looped {
+            index %= self.samples.len();
+        }
+        let sample = *self.samples.get(index)?;
+        self.phase += self.step;
+        if let Some(remaining) = &mut self.remaining {
+            *remaining -= 1;
+        }
+        Some(sample)
+    }
+}
+
+fn melody_voice(track: &Track, waves: &[u8], key: u8, ticks: usize) -> Voice {
+    let (period, multiplier, pizzicato_periods) = OCTAVES[usize::from(key / 12).min(7)];
+    let wave = &waves[usize::from(track.wave).min(99) * 256..][..256];
+    let periods = if track.pizzicato {
+        pizzicato_periods
+    } else {
+        1
+    };
+    let samples = (0..period * periods)
+        .map(|i| f32::from(wave[i * 256 / period % 256] as i8) / 128.)
+        .collect();
+    let rate = (period as u32 * FREQUENCIES[usize::from(key % 12)] * multiplier / 8) as f64
+        + (f64::from(track.freq) - 1000.);
+    Voice {
+        samples,
+        looped: !track.pizzicato,
+        step: rate.max(0.) / f64::from(SAMPLE_RATE),
+        phase: 0.,
+        remaining: Some(ticks),
+    }
+}
+
+fn drum_voice(wave: u8, key: u8) -> Voice {
+    // a deterministic noise burst, longer for higher sample numbers
+    let length = 1500 + usize::from(wave % 12) * 400;
+    let mut seed = 0x1234_5678u32 ^ u32::from(wave);
+    let samples = (0..length)
+        .map(|i| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            let noise = f32::from((seed >> 16) as u16 as i16) / 32768.;
+            noise * (1. - i as f32 / length as f32).powi(3)
+        })
+        .collect();
+    Voice {
+        samples,
+        looped: false,
+        // Organya plays drums at this rate in Hz
+        step: (f64::from(key) * 800. + 100.) / f64::from(SAMPLE_RATE),
+        phase: 0.,
+        remaining: None,
+    }
+}
+
+/// Gain of an attenuation in hundredths of a decibel, as DirectSound takes them.
+fn attenuation(millibels: f32) -> f32 {
+    10f32.powf(millibels / 2000.)
+}
+
+/// Left and right gains of a volume (0 to 254) and pan (0 to 12, 6 is centered).
+fn gains(volume: u8, pan: u8) -> (f32, f32) {
+    let volume = attenuation((f32::from(volume) - 255.) * 8.);
+    let pan = (f32::from(pan) - 6.) * 256.;
+    (
+        volume * attenuation(-pan.max(0.)),
+        volume * attenuation(pan.min(0.)),
+    )
+}
+
+/// Render `song` to interleaved 16-bit stereo at [`SAMPLE_RATE`], repeating its loop `loops` times.
+pub fn render(song: &Organya, waves: &[u8], loops: usize) -> Vec<i16> {
+    assert!(waves.len() >= WAVE_TABLE_SIZE, "incomplete wave table");
+
+    let end = if song.repeat.1 > 0 {
+        song.repeat.1
+    } else {
+        song.tracks
+            .iter()
+            .flat_map(|t| &t.notes)
+            .map(|n| n.position + i32::from(n.length))
+            .max()
+            .unwrap_or(0)
+    };
+    let start = song.repeat.0.clamp(0, end);
+    let ticks = (0..end).chain((0..loops).flat_map(|_| start..end));
+
+    let tick_len = f64::from(song.wait) * f64::from(SAMPLE_RATE) / 1000.;
+    let max_len = MAX_SECONDS * SAMPLE_RATE as usize * 2;
+    let mut voices: [Option<Voice>; TRACKS] = Default::default();
+    let mut levels = [(200u8, 6u8); TRACKS];
+    let mut cursors = [0; TRACKS];
+    let mut previous = -1;
+    let mut carry = 0.;
+    let mut out = Vec::new();
+
+    for tick in ticks {
+        for (i, track) in song.tracks.iter().enumerate().take(TRACKS) {
+            if tick < previous {
+                cursors[i] = track.notes.partition_point(|n| n.position < tick);
+            }
+            while let Some(note) = track.notes.get(cursors[i]).filter(|n| n.position <= tick) {
+                cursors[i] += 1;
+                if note.position < tick {
+                    continue;
+                }
+                if note.key != UNCHANGED {
+                    voices[i] = Some(if i < MELODY_TRACKS {
+                        let length = (f64::from(note.length) * tick_len) as usize;
+                        melody_voice(track, waves, note.key, length)
+                    } else {
+                        drum_voice(track.wave, note.key)
+                    });
+                }
+                if note.volume != UNCHANGED {
+                    levels[i].0 = note.volume;
+                }
+                if note.pan != UNCHANGED {
+                    levels[i].1 = note.pan;
+                }
+            }
+        }
+        previous = tick;
+
+        let gains = levels.map(|(volume, pan)| gains(volume, pan));
+        carry += tick_len;
+        for _ in 0..carry as usize {
+            let (mut left, mut right) = (0., 0.);
+            for (voice, (gain_l, gain_r)) in voices.iter_mut().zip(gains) {
+                if let Some(sample) = voice.as_mut().and_then(Voice::next) {
+                    left += sample * gain_l;
+                    right += sample * gain_r;
+                }
+            }
+            for sample in [left, right] {
+                out.push((sample * 0.25 * 32767.).clamp(-32768., 32767.) as i16);
+            }
+        }
+        carry = carry.fract();
+        if out.len() >= max_len {
+            out.truncate(max_len);
+            break;
+        }
+    }
+    out
+}
+
+/// A 16-bit stereo WAV file of `samples`.
+pub fn encode_wav(samples: &[i16]) -> Vec<u8> {
+    let data_len = (samples.len() * 2) as u32;
+    let mut wav = Vec::with_capacity(44 + samples.len() * 2);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    // PCM, 2 channels
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(SAMPLE_RATE * 4).to_le_bytes());
+    wav.extend_from_slice(&4u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        wav.extend_from_slice(&sample.to_le_bytes());
+    }
+    wav
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A square wave for every waveform.
+    fn waves() -> Vec<u8> {
+        (0..WAVE_TABLE_SIZE)
+            .map(|i| if i % 256 < 128 { 100 } else { (-100i8) as u8 })
+            .collect()
+    }
+
+    /// A song with `notes` on its first track, and nothing else.
+    fn song_bytes(wait: u16, repeat: (i32, i32), pizzicato: bool, notes: &[Note]) -> Vec<u8> {
+        let mut data = b"Org-02".to_vec();
+        data.extend_from_slice(&wait.to_le_bytes());
+        data.extend_from_slice(&[4, 4]);
+        data.extend_from_slice(&repeat.0.to_le_bytes());
+        data.extend_from_slice(&repeat.1.to_le_bytes());
+        for track in 0..TRACKS {
+            let count = if track == 0 { notes.len() as u16 } else { 0 };
+            data.extend_from_slice(&1000u16.to_le_bytes());
+            data.extend_from_slice(&[0, u8::from(pizzicato)]);
+            data.extend_from_slice(&count.to_le_bytes());
+        }
+        for note in notes {
+            data.extend_from_slice(&note.position.to_le_bytes());
+        }
+        data.extend(notes.iter().map(|n| n.key));
+        data.extend(notes.iter().map(|n| n.length));
+        data.extend(notes.iter().map(|n| n.volume));
+        data.extend(notes.iter().map(|n| n.pan));
+        data
+    }
+
+    fn note(position: i32, key: u8, length: u8, pan: u8) -> Note {
+        Note {
+            position,
+            key,
+            length,
+            volume: 200,
+            pan,
+        }
+    }
+
+    #[test]
+    fn parse_notes() {
+        let notes = [note(0, 45, 4, 6), note(8, UNCHANGED, 0, 0)];
+        let song = parse(&song_bytes(100, (0, 16), true, &notes)).unwrap();
+        assert_eq!(song.wait, 100);
+        assert_eq!(song.repeat, (0, 16));
+        assert_eq!(song.tracks.len(), TRACKS);
+        assert!(song.tracks[0].pizzicato);
+        assert_eq!(song.tracks[0].notes, notes);
+        assert!(song.tracks[1].notes.is_empty());
+    }
+
+    #[test]
+    fn reject_invalid() {
+        assert_eq!(parse(b"RIFF0000"), Err(OrgError::BadMagic));
+        let data = song_bytes(100, (0, 16), false, &[note(0, 45, 4, 6)]);
+        assert_eq!(parse(&data[..data.len() - 1]), Err(OrgError::Truncated));
+    }
+
+    #[test]
+    fn render_length_and_loops() {
+        let song = parse(&song_bytes(100, (8, 16), false, &[note(0, 45, 4, 6)])).unwrap();
+        let once = render(&song, &waves(), 0);
+        // 16 ticks of 100ms, stereo
+        assert_eq!(once.len(), 16 * 4410 * 2);
+        let looped = render(&song, &waves(), 2);
+        assert_eq!(looped.len(), (16 + 2 * 8) * 4410 * 2);
+    }
+
+    #[test]
+    fn note_plays_for_its_length() {
+        let song = parse(&song_bytes(100, (0, 8), false, &[note(0, 45, 4, 6)])).unwrap();
+        let pcm = render(&song, &waves(), 0);
+        let (playing, silent) = pcm.split_at(4 * 4410 * 2);
+        assert!(playing.iter().any(|&s| s != 0));
+        assert!(silent.iter().all(|&s| s == 0));
+    }
+
+    #[test]
+    fn note_pitch() {
+        // key 45 is A at 440Hz, a period of about 100 samples
+        let song = parse(&song_bytes(1000, (0, 1), false, &[note(0, 45, 1, 6)])).unwrap();
+        let pcm = render(&song, &waves(), 0);
+        let left: Vec<i16> = pcm.iter().step_by(2).copied().collect();
+        let rising = left.windows(2).filter(|w| w[0] <= 0 && w[1] > 0).count();
+        assert!((438..=442).contains(&rising), "{rising} periods");
+    }
+
+    #[test]
+    fn pizzicato_stops_early() {
+        let song = parse(&song_bytes(1000, (0, 1), true, &[note(0, 45, 1, 6)])).unwrap();
+        let pcm = render(&song, &waves(), 0);
+        assert!(pcm[..100].iter().any(|&s| s != 0));
+        assert!(pcm[pcm.len() / 2..].iter().all(|&s| s == 0));
+    }
+
+    #[test]
+    fn pan() {
+        let song = parse(&song_bytes(100, (0, 1), false, &[note(0, 45, 1, 12)])).unwrap();
+        let pcm = render(&song, &waves(), 0);
+        let loudness = |channel: usize| {
+            pcm.iter()
+                .skip(channel)
+                .step_by(2)
+                .map(|&s| i64::from(s).abs())
+                .sum::<i64>()
+        };
+        assert!(loudness(1) > loudness(0) * 4);
+    }
+
+    #[test]
+    fn wav_header() {
+        let wav = encode_wav(&[1, -1, 2, -2]);
+        assert_eq!(&wav[..4], b"RIFF");
+        assert_eq!(&wav[8..16], b"WAVEfmt ");
+        assert_eq!(u32::from_le_bytes(wav[4..8].try_into().unwrap()), 36 + 8);
+        assert_eq!(
+            u32::from_le_bytes(wav[24..28].try_into().unwrap()),
+            SAMPLE_RATE
+        );
+        assert_eq!(&wav[36..40], b"data");
+        assert_eq!(wav.len(), 44 + 8);
+        assert_eq!(i16::from_le_bytes([wav[46], wav[47]]), -1);
+    }
+}
@@ -0,0 +1,31 @@
+//! Stage entities (`.pxe`): the NPCs, doors and save points placed on a map.
+
+pub struct Entity {
+    /// Position, in tiles.
+    pub x: u16,
+    pub y: u16,
+    pub kind: u16,
+}
+
+pub fn parse(data: &[u8]) -> Option<Vec<Entity>> {
+    if !data.starts_with(b"PXE") {
+        return None;
+    }
+    let count = u32::from_le_bytes(data.get(4..8)?.try_into().ok()?) as usize;
+    let entities = data
+        .get(8..)?
+        .chunks_exact(12)
+        .take(count)
+        .map(|e| {
+            let field = |i: usize| u16::from_le_bytes([e[i * 2], e[i * 2 + 1]]);
+            Entity {
+                x: field(0),
+                y: field(1),
+                // 2: flag, 3: event
+                kind: field(4),
+                // 5: bits
+            }
+        })
+        .collect();
+    Some(entities)
+}
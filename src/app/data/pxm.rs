@@ -0,0 +1,53 @@
+//! Stage tile maps (`.pxm`) and tile attributes (`.pxa`).
+
+pub struct TileMap {
+    pub width: usize,
+    pub height: usize,
+    pub tiles: Vec<u8>,
+}
+
+impl TileMap {
+    /// Width and height, from the header alone.
+    pub fn size(data: &[u8]) -> Option<[usize; 2]> {
+        if !data.starts_with(b"PXM") {
+            return None;
+        }
+        let width = u16::from_le_bytes(data.get(4..6)?.try_into().ok()?) as usize;
+        let height = u16::from_le_bytes(data.get(6..8)?.try_into().ok()?) as usize;
+        Some([width, height])
+    }
+
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let [width, height] = Self::size(data)?;
+        let tiles = data.get(8..8 + width * height)?.to_vec();
+        Some(Self {
+            width,
+            height,
+            tiles,
+        })
+    }
+
+    pub fn tile(&self, x: usize, y: usize) -> u8 {
+        self.tiles[y * self.width + x]
+    }
+}
+
+/// Rough kind of a tile, from its attribute in the `.pxa` file.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TileKind {
+    Air,
+    Solid,
+    Slope,
+    Hurt,
+    Water,
+}
+
+pub fn tile_kind(attribute: u8) -> TileKind {
+    match attribute {
+        0x41 | 0x43 | 0x46 | 0x61 => TileKind::Solid,
+        0x50..=0x57 | 0x70..=0x77 => TileKind::Slope,
+        0x42 | 0x62 => TileKind::Hurt,
+        0x60 | 0xA0..=0xA3 => TileKind::Water,
+        _ => TileKind::Air,
+    }
+}
@@ -0,0 +1,230 @@
+//! Stage tables, in the formats used by the different releases.
+
+/// One stage table entry. Only the fields the editor uses are kept.
+#[derive(Clone, Debug, Default)]
+pub struct Stage {
+    pub tileset: String,
+    pub filename: String,
+    pub name: String,
+}
+
+/// `stage.tbl`, from Cave Story+ and doukutsu-rs.
+const TBL_ENTRY: usize = 0xE5;
+/// `mrmap.bin`, from the Moustache Rider's stage table patch.
+const MRMAP_ENTRY: usize = 0x74;
+/// `stage.sect`, the stage table section extracted from freeware `Doukutsu.exe`.
+const SECT_ENTRY: usize = 0xC8;
+
+pub const TABLE_FILES: [&str; 4] = ["stage.tbl", "mrmap.bin", "stage.sect", "Doukutsu.exe"];
+
+/// Where the freeware `Doukutsu.exe` keeps its 95 stages, unless patched into a `.csmap` section.
+const EXE_TABLE_OFFSET: usize = 0x937B0;
+const EXE_TABLE_STAGES: usize = 95;
+
+fn cstr(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+/// Parse a stage table, picking the format from its file name.
+pub fn parse(file_name: &str, data: &[u8]) -> Option<Vec<Stage>> {
+    match file_name {
+        "stage.tbl" => Some(parse_tbl(data)),
+        "mrmap.bin" => parse_mrmap(data),
+        "stage.sect" => Some(parse_sect(data)),
+        "Doukutsu.exe" => parse_exe(data),
+        _ => None,
+    }
+}
+
+fn parse_tbl(data: &[u8]) -> Vec<Stage> {
+    data.chunks_exact(TBL_ENTRY)
+        .map(|e| Stage {
+            tileset: cstr(&e[0x00..0x20]),
+            filename: cstr(&e[0x20..0x40]),
+            // 0x40: background type, 0x44: background, 0x64: npc sheets, 0xA4: boss
+            // 0xA5: japanese name
+            name: cstr(&e[0xC5..0xE5]),
+        })
+        .collect()
+}
+
+fn parse_mrmap(data: &[u8]) -> Option<Vec<Stage>> {
+    let count = u32::from_le_bytes(data.get(..4)?.try_into().ok()?) as usize;
+    let stages = data[4..]
+        .chunks_exact(MRMAP_ENTRY)
+        .take(count)
+        .map(|e| Stage {
+            tileset: cstr(&e[0x00..0x10]),
+            filename: cstr(&e[0x10..0x20]),
+            // 0x20: background type, 0x21: background, 0x31: npc sheets, 0x51: boss
+            name: cstr(&e[0x52..0x74]),
+        })
+        .collect();
+    Some(stages)
+}
+
+fn parse_sect(data: &[u8]) -> Vec<Stage> {
+    data.chunks_exact(SECT_ENTRY)
+        .map(|e| Stage {
+            tileset: cstr(&e[0x00..0x20]),
+            filename: cstr(&e[0x20..0x40]),
+            // 0x40: background type, 0x44: background, 0x64: npc sheets, 0xA4: boss
+            name: cstr(&e[0xA5..0xC8]),
+        })
+        .collect()
+}
+
+/// Find the stage table in the executable, following the `.csmap` section if there is one.
+fn parse_exe(data: &[u8]) -> Option<Vec<Stage>> {
+    let u16_at = |offset: usize| -> Option<usize> {
+        Some(u16::from_le_bytes(data.get(offset..offset + 2)?.try_into().ok()?) as usize)
+    };
+    let u32_at = |offset: usize| -> Option<usize> {
+        Some(u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?) as usize)
+    };
+
+    let pe = u32_at(0x3C)?;
+    if data.get(pe..pe + 4)? != b"PE\0\0" {
+        return None;
+    }
+    let sections = u16_at(pe + 6)?;
+    let table = pe + 24 + u16_at(pe + 20)?;
+    let csmap = (0..sections).find_map(|i| {
+        let header = table + i * 40;
+        if data.get(header..header + 8)? != b".csmap\0\0" {
+            return None;
+        }
+        let size = u32_at(header + 16)?;
+        let offset = u32_at(header + 20)?;
+        data.get(offset..offset + size)
+    });
+
+    let table = match csmap {
+        Some(table) => table,
+        None => data.get(EXE_TABLE_OFFSET..EXE_TABLE_OFFSET + EXE_TABLE_STAGES * SECT_ENTRY)?,
+    };
+    // the section is padded, stop at the first empty entry
+    Some(
+        parse_sect(table)
+            .into_iter()
+            .take_while(|s| !s.filename.is_empty() || !s.tileset.is_empty())
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An entry of `len` bytes with the given strings at their offsets.
+    fn entry(len: usize, fields: &[(usize, &str)]) -> Vec<u8> {
+        let mut data = vec![0; len];
+        for &(offset, text) in fields {
+            data[offset..offset + text.len()].copy_from_slice(text.as_bytes());
+        }
+        data
+    }
+
+    fn names(stages: &[Stage]) -> Vec<(&str, &str, &str)> {
+        stages
+            .iter()
+            .map(|s| (s.tileset.as_str(), s.filename.as_str(), s.name.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn parse_stage_tbl() {
+        let mut data = entry(
+            TBL_ENTRY,
+            &[(0x00, "Pens"), (0x20, "Pens1"), (0xC5, "Arthur's House")],
+        );
+        data.extend(entry(
+            TBL_ENTRY,
+            &[(0x00, "Cave"), (0x20, "Cave"), (0xC5, "First Cave")],
+        ));
+        // a trailing partial entry is ignored
+        data.extend([0; 10]);
+        let stages = parse("stage.tbl", &data).unwrap();
+        assert_eq!(
+            names(&stages),
+            [
+                ("Pens", "Pens1", "Arthur's House"),
+                ("Cave", "Cave", "First Cave"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_mrmap_bin() {
+        let mut data = 1u32.to_le_bytes().to_vec();
+        data.extend(entry(
+            MRMAP_ENTRY,
+            &[(0x00, "Pens"), (0x10, "Pens1"), (0x52, "Arthur's House")],
+        ));
+        // entries past the count are ignored
+        data.extend(entry(MRMAP_ENTRY, &[(0x00, "Cave"), (0x10, "Cave")]));
+        let stages = parse("mrmap.bin", &data).unwrap();
+        assert_eq!(names(&stages), [("Pens", "Pens1", "Arthur's House")]);
+        assert!(parse("mrmap.bin", &[1, 0]).is_none());
+    }
+
+    #[test]
+    fn parse_stage_sect() {
+        let data = entry(
+            SECT_ENTRY,
+            &[(0x00, "Cave"), (0x20, "Cave"), (0xA5, "First Cave")],
+        );
+        let stages = parse("stage.sect", &data).unwrap();
+        assert_eq!(names(&stages), [("Cave", "Cave", "First Cave")]);
+        assert!(parse("stage.txt", &data).is_none());
+    }
+
+    /// A PE header at 0x40 with a single `.csmap` section holding `table`.
+    fn exe_with_csmap(table: &[u8]) -> Vec<u8> {
+        const PE: usize = 0x40;
+        const SECTION: usize = PE + 24;
+        const DATA: usize = 0x100;
+        let mut data = vec![0; DATA];
+        data[0x3C..0x40].copy_from_slice(&(PE as u32).to_le_bytes());
+        data[PE..PE + 4].copy_from_slice(b"PE\0\0");
+        data[PE + 6..PE + 8].copy_from_slice(&1u16.to_le_bytes());
+        // no optional header, the section table follows the file header
+        data[PE + 20..PE + 22].copy_from_slice(&0u16.to_le_bytes());
+        data[SECTION..SECTION + 8].copy_from_slice(b".csmap\0\0");
+        data[SECTION + 16..SECTION + 20].copy_from_slice(&(table.len() as u32).to_le_bytes());
+        data[SECTION + 20..SECTION + 24].copy_from_slice(&(DATA as u32).to_le_bytes());
+        data.extend_from_slice(table);
+        data
+    }
+
+    #[test]
+    fn parse_exe_csmap_section() {
+        let mut table = entry(
+            SECT_ENTRY,
+            &[(0x00, "Cave"), (0x20, "Cave"), (0xA5, "First Cave")],
+        );
+        // padding after the last stage
+        table.extend(vec![0; SECT_ENTRY * 2]);
+        let stages = parse("Doukutsu.exe", &exe_with_csmap(&table)).unwrap();
+        assert_eq!(names(&stages), [("Cave", "Cave", "First Cave")]);
+    }
+
+    #[test]
+    fn parse_exe_fixed_table() {
+        let mut data = exe_with_csmap(&[]);
+        // rename the section so the fixed offset is used
+        data[0x40 + 24..0x40 + 32].copy_from_slice(b".rdata\0\0");
+        data.resize(EXE_TABLE_OFFSET, 0);
+        for _ in 0..EXE_TABLE_STAGES {
+            data.extend(entry(SECT_ENTRY, &[(0x00, "Pens"), (0x20, "Pens1")]));
+        }
+        let stages = parse("Doukutsu.exe", &data).unwrap();
+        assert_eq!(stages.len(), EXE_TABLE_STAGES);
+        assert_eq!(stages[0].filename, "Pens1");
+
+        data.truncate(EXE_TABLE_OFFSET);
+        assert!(parse("Doukutsu.exe", &data).is_none());
+        assert!(parse("Doukutsu.exe", b"MZ").is_none());
+    }
+}
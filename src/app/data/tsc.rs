@@ -0,0 +1,249 @@
+//! TSC scripts, the game's event language.
+
+/// Decrypt a `.tsc` file: every byte but the middle one is shifted by the middle one.
+pub fn decrypt(data: &[u8]) -> Vec<u8> {
+    let middle = data.len() / 2;
+    let Some(&key) = data.get(middle) else {
+        return Vec::new();
+    };
+    data.iter()
+        .enumerate()
+        .map(|(i, &b)| if i == middle { b } else { b.wrapping_sub(key) })
+        .collect()
+}
+
+/// Split a decrypted script into `(event number, body)` pairs.
+pub fn events(script: &str) -> Vec<(u16, &str)> {
+    let mut events = Vec::new();
+    let mut rest = script;
+    while let Some(start) = rest.find('#') {
+        let after = &rest[start + 1..];
+        let body_end = after.find("\n#").map_or(after.len(), |i| i + 1);
+        if let Some(number) = after.get(..4).and_then(|n| n.parse().ok()) {
+            let body = after[4..body_end].trim_start_matches(['\r', '\n']);
+            events.push((number, body));
+        }
+        rest = &after[body_end..];
+    }
+    events
+}
+
+/// Argument count of the commands known to the game, doukutsu-rs and Cave Story+.
+fn arg_count(name: &str) -> Option<usize> {
+    let count = match name {
+        "AE+" | "CAT" | "CIL" | "CLO" | "CLR" | "CPS" | "CRE" | "CSS" | "END" | "ESC" | "FLA"
+        | "FMU" | "FRE" | "HMC" | "INI" | "KEY" | "LDP" | "MLP" | "MM0" | "MNA" | "MS2" | "MS3"
+        | "MSG" | "NOD" | "PRI" | "RMU" | "SAT" | "SLP" | "SMC" | "SPS" | "STC" | "SVP" | "TUR"
+        | "WAS" | "ZAM" => 0,
+        "AM-" | "BOA" | "BSL" | "CMU" | "DNA" | "DNP" | "EQ+" | "EQ-" | "EVE" | "FAC" | "FAI"
+        | "FAO" | "FL+" | "FL-" | "FOM" | "GIT" | "IT+" | "IT-" | "LI+" | "ML+" | "MP+" | "MPJ"
+        | "MYB" | "MYD" | "NUM" | "QUA" | "SIL" | "SK+" | "SK-" | "SOU" | "SSS" | "UNI" | "WAI"
+        | "XX1" | "YNJ" | "ACH" => 1,
+        "AM+" | "AMJ" | "ECJ" | "FLJ" | "FOB" | "FON" | "ITJ" | "MOV" | "NCJ" | "PS+" | "SKJ"
+        | "SMP" | "UNJ" => 2,
+        "ANP" | "CMP" | "CNP" | "INP" | "TAM" => 3,
+        "MNP" | "SNP" | "TRA" => 4,
+        _ => return None,
+    };
+    Some(count)
+}
+
+/// Commands whose first argument is an event flag: set, clear and jump if set.
+pub const FLAG_COMMANDS: [&str; 3] = ["FL+", "FL-", "FLJ"];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Token<'a> {
+    /// A command such as `<FL+0200`: its name without `<`, its arguments and its source.
+    Command {
+        name: &'a str,
+        args: Vec<u16>,
+        source: &'a str,
+    },
+    Text(&'a str),
+}
+
+/// Split an event body into commands and text.
+pub fn tokens(body: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = body;
+    let mut text_len = 0;
+    while let Some(offset) = rest[text_len..].find('<') {
+        let start = text_len + offset;
+        let Some((name, count)) = rest
+            .get(start + 1..start + 4)
+            .and_then(|name| Some((name, arg_count(name)?)))
+        else {
+            text_len = start + 1;
+            continue;
+        };
+        if start > 0 {
+            tokens.push(Token::Text(&rest[..start]));
+        }
+        let end = (start + 4 + count * 5 - count.min(1)).min(rest.len());
+        let end = (end..=rest.len())
+            .find(|&i| rest.is_char_boundary(i))
+            .unwrap_or(rest.len());
+        let args = rest[start + 4..end]
+            .split(':')
+            .filter(|arg| !arg.is_empty())
+            .map(|arg| number(arg.as_bytes()))
+            .collect();
+        tokens.push(Token::Command {
+            name,
+            args,
+            source: &rest[start..end],
+        });
+        rest = &rest[end..];
+        text_len = 0;
+    }
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest));
+    }
+    tokens
+}
+
+/// Commands of an event body, with their arguments.
+pub fn commands(body: &str) -> Vec<(&str, Vec<u16>)> {
+    tokens(body)
+        .into_iter()
+        .filter_map(|token| match token {
+            Token::Command { name, args, .. } => Some((name, args)),
+            Token::Text(_) => None,
+        })
+        .collect()
+}
+
+/// Commands which leave the event when their condition holds.
+const CONDITIONAL_JUMPS: [&str; 9] = [
+    "AMJ", "ECJ", "FLJ", "ITJ", "MPJ", "NCJ", "SKJ", "UNJ", "YNJ",
+];
+/// Commands after which the rest of the event doesn't run.
+const EXITS: [&str; 3] = ["END", "EVE", "TRA"];
+
+/// The commands of [`commands`] which run along with the one at `at`: those after
+/// the last conditional jump before it, up to the end of the event.
+pub fn branch<'c, 'a>(commands: &'c [(&'a str, Vec<u16>)], at: usize) -> &'c [(&'a str, Vec<u16>)] {
+    let start = commands[..at]
+        .iter()
+        .rposition(|(name, _)| CONDITIONAL_JUMPS.contains(name))
+        .map_or(0, |jump| jump + 1);
+    let end = commands[at..]
+        .iter()
+        .position(|(name, _)| EXITS.contains(name))
+        .map_or(commands.len(), |exit| at + exit + 1);
+    &commands[start..end]
+}
+
+/// Parse a TSC number the way the game does: each character is offset from `'0'`.
+fn number(arg: &[u8]) -> u16 {
+    arg.iter().fold(0u16, |acc, &c| {
+        acc.wrapping_mul(10)
+            .wrapping_add(u16::from(c.wrapping_sub(b'0')))
+    })
+}
+
+/// The text an event shows, with commands and their arguments removed.
+pub fn text(body: &str) -> String {
+    tokens(body)
+        .into_iter()
+        .filter_map(|token| match token {
+            Token::Text(text) => Some(text.replace('\r', "")),
+            Token::Command { .. } => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encrypt a script the way the game's files are: the middle byte is the key.
+    fn encrypt(script: &[u8], key: u8) -> Vec<u8> {
+        let middle = script.len() / 2;
+        let mut data = script.to_vec();
+        data[middle] = key;
+        for (i, b) in data.iter_mut().enumerate() {
+            if i != middle {
+                *b = b.wrapping_add(key);
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn decrypt_keeps_the_key_byte() {
+        let data = encrypt(b"#0090\r\n<END", 0xF0);
+        let script = decrypt(&data);
+        assert_eq!(script[..5], *b"#0090");
+        assert_eq!(script[data.len() / 2], 0xF0);
+        assert_eq!(script[data.len() - 3..], *b"END");
+        assert!(decrypt(&[]).is_empty());
+    }
+
+    #[test]
+    fn split_events() {
+        let script = "junk#0090\r\n<MNA<END\r\n#0100\r\nHi<NOD<END\r\n#abcd\r\n<END";
+        assert_eq!(
+            events(script),
+            [(90, "<MNA<END\r\n"), (100, "Hi<NOD<END\r\n")]
+        );
+    }
+
+    #[test]
+    fn known_arg_counts() {
+        assert_eq!(arg_count("END"), Some(0));
+        assert_eq!(arg_count("FL+"), Some(1));
+        assert_eq!(arg_count("AM+"), Some(2));
+        assert_eq!(arg_count("TAM"), Some(3));
+        assert_eq!(arg_count("TRA"), Some(4));
+        assert_eq!(arg_count("???"), None);
+    }
+
+    #[test]
+    fn tokens_split_commands_and_text() {
+        let body = "<FL+0200<AM+0005:0010Got it!<NOD<CMU0a01<END";
+        assert_eq!(
+            tokens(body),
+            [
+                Token::Command {
+                    name: "FL+",
+                    args: vec![200],
+                    source: "<FL+0200",
+                },
+                Token::Command {
+                    name: "AM+",
+                    args: vec![5, 10],
+                    source: "<AM+0005:0010",
+                },
+                Token::Text("Got it!"),
+                Token::Command {
+                    name: "NOD",
+                    args: vec![],
+                    source: "<NOD",
+                },
+                // digits are offset from '0', like the game reads them
+                Token::Command {
+                    name: "CMU",
+                    args: vec![4901],
+                    source: "<CMU0a01",
+                },
+                Token::Command {
+                    name: "END",
+                    args: vec![],
+                    source: "<END",
+                },
+            ]
+        );
+        assert_eq!(text("<MSGa <b>\r\n<NOD"), "a <b>\n");
+    }
+
+    #[test]
+    fn branch_stops_at_jumps_and_exits() {
+        let commands = commands("<FLJ0200:0101<FL+0200<AM+0005:0010<END<ITJ0002:0102<FL+0300<END");
+        assert_eq!(
+            branch(&commands, 2),
+            [("FL+", vec![200]), ("AM+", vec![5, 10]), ("END", vec![]),]
+        );
+        assert_eq!(branch(&commands, 5), [("FL+", vec![300]), ("END", vec![])]);
+    }
+}
@@ -0,0 +1,61 @@
+//! What the editor knows of the vanilla game, used without a data folder.
+//!
+//! The tables are generated by `build.rs` from the files in `tables/`.
+
+use super::xref::Pickup;
+
+/// A vanilla weapon trade, see [`super::xref::Trade`].
+pub struct Trade {
+    pub from: i32,
+    pub to: i32,
+    pub ammo: i32,
+    pub flags_set: &'static [u16],
+    pub flags_cleared: &'static [u16],
+    pub items_added: &'static [i32],
+    pub items_removed: &'static [i32],
+}
+
+include!(concat!(env!("OUT_DIR"), "/vanilla.rs"));
+
+pub fn description(table: &[(i32, &'static str)], id: i32) -> Option<&'static str> {
+    table
+        .iter()
+        .find(|(entry, _)| *entry == id)
+        .map(|(_, description)| *description)
+}
+
+pub fn map_name(map: i32) -> Option<&'static str> {
+    let name = STAGES.get(usize::try_from(map).ok()?)?;
+    Some(*name).filter(|name| !name.is_empty())
+}
+
+/// EXP held by each level of a vanilla weapon; EXP starts over at each level.
+pub fn arms_level(weapon: i32) -> Option<[i32; 3]> {
+    ARMS_LEVELS.get(usize::try_from(weapon).ok()?).copied()
+}
+
+/// Max ammo of a vanilla weapon when obtained, and what a missile expansion adds;
+/// `None` for weapons with infinite ammo.
+pub fn ammo(weapon: i32) -> Option<(i32, i32)> {
+    ARMS_AMMO
+        .iter()
+        .find(|(id, ..)| *id == weapon)
+        .map(|&(_, max, expansion)| (max, expansion))
+}
+
+/// The event a hard-coded boss (`<BSL0000`) of `map` runs when it dies.
+pub fn boss_death_event(map: Option<i32>) -> u16 {
+    const DEFAULT: u16 = 1000;
+    BOSS_DEATH_EVENTS
+        .iter()
+        .find(|(entry, _)| Some(*entry) == map)
+        .map_or(DEFAULT, |&(_, event)| event)
+}
+
+/// Name of the vanilla boss whose defeat sets `flag`.
+pub fn boss_name(flag: u16) -> Option<&'static str> {
+    BOSSES
+        .iter()
+        .find(|(_, entry, _)| *entry == flag)
+        .map(|(.., name)| *name)
+}
@@ -0,0 +1,540 @@
+//! Where each event flag is used across every script of a data folder.
+
+use std::collections::BTreeMap;
+
+use super::{index::FileIndex, read_script, tsc, vanilla, NameTable};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Set,
+    Clear,
+    Check,
+}
+
+impl Action {
+    fn from_command(name: &str) -> Option<Self> {
+        match name {
+            "FL+" => Some(Action::Set),
+            "FL-" => Some(Action::Clear),
+            "FLJ" => Some(Action::Check),
+            _ => None,
+        }
+    }
+
+    pub fn verb(self) -> &'static str {
+        match self {
+            Action::Set => "sets",
+            Action::Clear => "clears",
+            Action::Check => "checks",
+        }
+    }
+}
+
+pub struct FlagUse {
+    /// The map owning the script, or the script's file name.
+    pub script: String,
+    pub event: u16,
+    pub action: Action,
+}
+
+#[derive(Default)]
+pub struct FlagIndex(BTreeMap<usize, Vec<FlagUse>>);
+
+/// Every script with its label: the map owning it, or its file name.
+fn scripts<'a>(
+    files: &'a FileIndex,
+    names: &'a NameTable,
+) -> impl Iterator<Item = (String, String)> + 'a {
+    map_scripts(files, names).map(|(_, label, script)| (label, script))
+}
+
+/// Like [`scripts`], with the id of the map owning each script.
+fn map_scripts<'a>(
+    files: &'a FileIndex,
+    names: &'a NameTable,
+) -> impl Iterator<Item = (Option<i32>, String, String)> + 'a {
+    files.with_extension("tsc").filter_map(|file_name| {
+        let script = read_script(files, file_name)?;
+        let stem = file_name.trim_end_matches(".tsc");
+        let map = names
+            .stages
+            .iter()
+            .position(|stage| stage.filename.eq_ignore_ascii_case(stem))
+            .map(|map| map as i32);
+        let label = map
+            .and_then(|map| names.map(map))
+            .map_or_else(|| file_name.to_owned(), str::to_owned);
+        Some((map, label, script))
+    })
+}
+
+impl FlagIndex {
+    pub fn build(files: &FileIndex, names: &NameTable) -> Self {
+        let mut index = Self::default();
+        for (label, script) in scripts(files, names) {
+            for (event, body) in tsc::events(&script) {
+                for (name, args) in tsc::commands(body) {
+                    let (Some(action), Some(&flag)) = (Action::from_command(name), args.first())
+                    else {
+                        continue;
+                    };
+                    index.0.entry(flag.into()).or_default().push(FlagUse {
+                        script: label.clone(),
+                        event,
+                        action,
+                    });
+                }
+            }
+        }
+        for uses in index.0.values_mut() {
+            uses.sort_by(|a, b| (&a.script, a.event).cmp(&(&b.script, b.event)));
+        }
+        index
+    }
+
+    pub fn uses(&self, flag: usize) -> &[FlagUse] {
+        self.0.get(&flag).map_or(&[], Vec::as_slice)
+    }
+
+    /// Flags used by at least one script, in order.
+    pub fn flags(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0.keys().copied()
+    }
+
+    /// One line per use, for tooltips.
+    pub fn describe(&self, flag: usize) -> String {
+        let uses = self.uses(flag);
+        if uses.is_empty() {
+            return format!("flag {flag} is not used by any script");
+        }
+        uses.iter()
+            .map(|u| format!("{} #{:04} {} it", u.script, u.event, u.action.verb()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// An event giving a weapon or an item.
+pub struct Source {
+    pub script: String,
+    pub event: u16,
+    /// Flags the event sets or checks.
+    pub flags: Vec<u16>,
+}
+
+/// Events giving each weapon (`<AM+`) and item (`<IT+`), by id.
+pub fn sources(
+    files: &FileIndex,
+    names: &NameTable,
+) -> (BTreeMap<i32, Vec<Source>>, BTreeMap<i32, Vec<Source>>) {
+    let (mut weapons, mut items) = (BTreeMap::new(), BTreeMap::new());
+    for (label, script) in scripts(files, names) {
+        for (event, body) in tsc::events(&script) {
+            let commands = tsc::commands(body);
+            let mut flags: Vec<u16> = commands
+                .iter()
+                .filter(|(name, _)| Action::from_command(name).is_some())
+                .filter_map(|(_, args)| args.first().copied())
+                .collect();
+            flags.sort_unstable();
+            flags.dedup();
+
+            for (name, args) in &commands {
+                let given = match *name {
+                    "AM+" => &mut weapons,
+                    "IT+" => &mut items,
+                    _ => continue,
+                };
+                let Some(&id) = args.first() else {
+                    continue;
+                };
+                given
+                    .entry(i32::from(id))
+                    .or_insert_with(Vec::new)
+                    .push(Source {
+                        script: label.clone(),
+                        event,
+                        flags: flags.clone(),
+                    });
+            }
+        }
+    }
+    (weapons, items)
+}
+
+/// A weapon trade (`<TAM`), with what its branch of the event does to flags and items.
+#[derive(Clone)]
+pub struct Trade {
+    pub from: i32,
+    pub to: i32,
+    /// Added to the weapon's ammo and max ammo.
+    pub ammo: i32,
+    /// Where the trade happens, if it was read from a script.
+    pub source: Option<(String, u16)>,
+    pub flags_set: Vec<u16>,
+    pub flags_cleared: Vec<u16>,
+    pub items_added: Vec<i32>,
+    pub items_removed: Vec<i32>,
+}
+
+/// Every `<TAM` of the scripts, once per weapons and ammo, with the flags and items of its branch.
+pub fn trades(files: &FileIndex, names: &NameTable) -> Vec<Trade> {
+    let mut trades: Vec<Trade> = Vec::new();
+    for (label, script) in scripts(files, names) {
+        for (event, body) in tsc::events(&script) {
+            let commands = tsc::commands(body);
+            for (at, (_, args)) in commands
+                .iter()
+                .enumerate()
+                .filter(|(_, (name, _))| *name == "TAM")
+            {
+                let &[from, to, ammo] = args.as_slice() else {
+                    continue;
+                };
+                let (from, to, ammo) = (from.into(), to.into(), ammo.into());
+                if trades
+                    .iter()
+                    .any(|t| (t.from, t.to, t.ammo) == (from, to, ammo))
+                {
+                    continue;
+                }
+                let branch = tsc::branch(&commands, at);
+                let first_args = |command: &str| -> Vec<u16> {
+                    branch
+                        .iter()
+                        .filter(|(name, _)| *name == command)
+                        .filter_map(|(_, args)| args.first().copied())
+                        .collect()
+                };
+                trades.push(Trade {
+                    from,
+                    to,
+                    ammo,
+                    source: Some((label.clone(), event)),
+                    flags_set: first_args("FL+"),
+                    flags_cleared: first_args("FL-"),
+                    items_added: first_args("IT+").into_iter().map(i32::from).collect(),
+                    items_removed: first_args("IT-").into_iter().map(i32::from).collect(),
+                });
+            }
+        }
+    }
+    trades
+}
+
+/// What picking up a collectible gives.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pickup {
+    /// Max health added by a Life Capsule (`<ML+`); 0 when unknown, as in the
+    /// vanilla table.
+    Life(i32),
+    /// A weapon and its ammo (`<AM+`); for a weapon already held, like the
+    /// Missile Launcher, the ammo expands its max ammo.
+    Weapon { weapon: i32, ammo: i32 },
+    /// An item (`<IT+`).
+    Item(i32),
+}
+
+/// A one-time pickup, remembered by the flag its event sets.
+pub struct Collectible {
+    pub script: String,
+    /// The event giving it, if read from a script.
+    pub event: Option<u16>,
+    pub flag: u16,
+    /// What the event gives, first found first. A missile expansion has one
+    /// `<AM+` per launcher, in branches for whichever is held.
+    pub pickups: Vec<Pickup>,
+}
+
+impl Collectible {
+    pub fn pickup(&self) -> Pickup {
+        self.pickups[0]
+    }
+}
+
+/// Every event setting a flag and raising max health or giving a weapon or an item, in script order.
+/// Items count only when the event neither trades nor shows a face, as a hidden pickup does.
+/// Every `<AM+` of the first event setting a flag is kept, along with those of the
+/// events its `<AMJ` jump to; other pickups only the first.
+pub fn collectibles(files: &FileIndex, names: &NameTable) -> Vec<Collectible> {
+    let mut collectibles: Vec<Collectible> = Vec::new();
+    for (label, script) in scripts(files, names) {
+        let events = tsc::events(&script);
+        for &(event, body) in &events {
+            let commands = tsc::commands(body);
+            let Some(flag) = commands
+                .iter()
+                .find(|(name, _)| *name == "FL+")
+                .and_then(|(_, args)| args.first().copied())
+            else {
+                continue;
+            };
+            // items given in a trade or by someone talking are part of the story, not hidden
+            let story = commands
+                .iter()
+                .any(|(name, _)| matches!(*name, "TAM" | "IT-" | "FAC"));
+            // the events a weapon check jumps to give the other branches' ammo
+            let branches: Vec<_> = commands
+                .iter()
+                .filter(|(name, _)| *name == "AMJ")
+                .filter_map(|(_, args)| args.get(1))
+                .filter_map(|&to| events.iter().find(|(e, _)| *e == to))
+                .flat_map(|(_, body)| tsc::commands(body))
+                .filter(|(name, _)| *name == "AM+")
+                .collect();
+            for (name, args) in commands.iter().chain(&branches) {
+                let pickup = match (*name, args.as_slice()) {
+                    ("ML+", &[life, ..]) => Pickup::Life(life.into()),
+                    ("AM+", &[weapon, ..]) => Pickup::Weapon {
+                        weapon: weapon.into(),
+                        ammo: args.get(1).copied().map_or(0, i32::from),
+                    },
+                    ("IT+", &[item, ..]) if !story => Pickup::Item(item.into()),
+                    _ => continue,
+                };
+                match collectibles.iter_mut().find(|c| c.flag == flag) {
+                    // another branch of a weapon pickup, such as the Super Missile's
+                    Some(c) => {
+                        let weapon = matches!(pickup, Pickup::Weapon { .. });
+                        if weapon && c.event == Some(event) && !c.pickups.contains(&pickup) {
+                            c.pickups.push(pickup);
+                        }
+                    }
+                    None => collectibles.push(Collectible {
+                        script: label.clone(),
+                        event: Some(event),
+                        flag,
+                        pickups: vec![pickup],
+                    }),
+                }
+            }
+        }
+    }
+    collectibles
+}
+
+/// A boss fight: an event showing a boss life bar (`<BSL`) for an NPC whose
+/// death event sets a flag.
+pub struct BossFight {
+    /// Name of the vanilla boss setting the same flag, if any.
+    pub name: Option<&'static str>,
+    pub script: String,
+    pub map: Option<i32>,
+    /// The event starting the fight and the one run when the boss dies, if read
+    /// from a script.
+    pub events: Option<(u16, u16)>,
+    /// The first flag the death event sets, taken as the defeated flag: scripts
+    /// mark the boss dead before moving the story on. Only a convention, so
+    /// it's shown along with the death event to be checked.
+    pub flag: u16,
+    /// The other flags the death event sets, as the story moves on.
+    pub story_flags: Vec<u16>,
+    /// Maps the death event sends the player to, which only come after the fight.
+    pub next_maps: Vec<i32>,
+}
+
+/// Most events a death event may chain through `<EVE` to reach a `<TRA`.
+const MAX_EVENT_CHAIN: usize = 8;
+
+pub fn boss_fights(files: &FileIndex, names: &NameTable) -> Vec<BossFight> {
+    let mut fights = Vec::new();
+    for (map, label, script) in map_scripts(files, names) {
+        let events = tsc::events(&script);
+        let body_of = |event: u16| {
+            events
+                .iter()
+                .find(|(e, _)| *e == event)
+                .map(|(_, body)| *body)
+        };
+        for (event, body) in &events {
+            for (name, args) in tsc::commands(body) {
+                let ("BSL", Some(&npc_event)) = (name, args.first()) else {
+                    continue;
+                };
+                // `<BSL0000` shows a hard-coded boss, which runs a fixed event when it dies
+                let death_event = match npc_event {
+                    0 => vanilla::boss_death_event(map),
+                    _ => npc_event,
+                };
+                let Some(death) = body_of(death_event) else {
+                    continue;
+                };
+                let mut flags = tsc::commands(death)
+                    .into_iter()
+                    .filter(|(name, _)| *name == "FL+")
+                    .filter_map(|(_, args)| args.first().copied());
+                let Some(flag) = flags.next() else {
+                    continue;
+                };
+                if fights.iter().any(|f: &BossFight| f.flag == flag) {
+                    continue;
+                }
+
+                let mut next_maps = Vec::new();
+                let mut chain = Some(death);
+                for _ in 0..MAX_EVENT_CHAIN {
+                    let Some(body) = chain.take() else {
+                        break;
+                    };
+                    for (name, args) in tsc::commands(body) {
+                        match (name, args.first()) {
+                            ("TRA", Some(&to)) => next_maps.push(i32::from(to)),
+                            ("EVE", Some(&next)) => chain = body_of(next),
+                            _ => {}
+                        }
+                    }
+                }
+                next_maps.retain(|&to| Some(to) != map);
+
+                fights.push(BossFight {
+                    name: vanilla::boss_name(flag),
+                    script: label.clone(),
+                    map,
+                    events: Some((*event, death_event)),
+                    flag,
+                    story_flags: flags.collect(),
+                    next_maps,
+                });
+            }
+        }
+    }
+    fights
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    /// A data folder holding `scripts`, encrypted as the game's are.
+    struct TestDir(PathBuf);
+
+    impl TestDir {
+        fn new(name: &str, scripts: &[(&str, &str)]) -> Self {
+            let dir = std::env::temp_dir().join(format!("xref-{name}-{}", std::process::id()));
+            std::fs::create_dir_all(&dir).unwrap();
+            for (file_name, script) in scripts {
+                // padded so the middle byte, the key, isn't part of the script
+                let mut data = script.as_bytes().to_vec();
+                data.resize(data.len() * 2 + 1, b'\n');
+                let middle = data.len() / 2;
+                let key = data[middle];
+                for (i, b) in data.iter_mut().enumerate() {
+                    if i != middle {
+                        *b = b.wrapping_add(key);
+                    }
+                }
+                std::fs::write(dir.join(file_name), data).unwrap();
+            }
+            Self(dir)
+        }
+
+        fn files(&self) -> FileIndex {
+            FileIndex::scan(&self.0)
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn flag_index_lists_uses() {
+        let dir = TestDir::new(
+            "flags",
+            &[
+                ("Pens1.tsc", "#0100\r\n<FLJ0200:0101<FL+0200<END\r\n"),
+                ("Cave.tsc", "#0200\r\n<FL-0200<FL+0300<END\r\n"),
+            ],
+        );
+        let index = FlagIndex::build(&dir.files(), &NameTable::default());
+        assert_eq!(index.flags().collect::<Vec<_>>(), [200, 300]);
+        assert_eq!(
+            index.describe(200),
+            "cave.tsc #0200 clears it\npens1.tsc #0100 checks it\npens1.tsc #0100 sets it"
+        );
+        assert!(index.uses(400).is_empty());
+        assert_eq!(index.describe(400), "flag 400 is not used by any script");
+    }
+
+    #[test]
+    fn trades_read_their_branch() {
+        let dir = TestDir::new(
+            "trades",
+            &[(
+                "Pole.tsc",
+                "#0100\r\n<ITJ0010:0101<TAM0002:0013:0000<FL+1644<IT+0010<IT-0008<END\r\n\
+                 #0101\r\n<FL-0500<TAM0002:0013:0000<FL+1645<END\r\n\
+                 #0102\r\n<FL+0501<ITJ0011:0103<TAM0003:0001:0010<FL-0721<END\r\n",
+            )],
+        );
+        let trades = trades(&dir.files(), &NameTable::default());
+        // the same trade is listed once, as first found
+        assert_eq!(trades.len(), 2);
+        let spur = &trades[0];
+        assert_eq!((spur.from, spur.to, spur.ammo), (2, 13, 0));
+        assert_eq!(spur.source, Some(("pole.tsc".to_owned(), 100)));
+        assert_eq!(spur.flags_set, [1644]);
+        assert_eq!(spur.items_added, [10]);
+        assert_eq!(spur.items_removed, [8]);
+
+        // the flag set before the jump belongs to the other branch
+        let snake = &trades[1];
+        assert_eq!((snake.from, snake.to, snake.ammo), (3, 1, 10));
+        assert!(snake.flags_set.is_empty());
+        assert_eq!(snake.flags_cleared, [721]);
+    }
+
+    #[test]
+    fn collectibles_keep_every_ammo_branch() {
+        let dir = TestDir::new(
+            "collectibles",
+            &[(
+                "Hell3.tsc",
+                "#0200\r\n<FL+1551<AMJ0010:0201<AM+0005:0005<END\r\n\
+                 #0201\r\n<AM+0010:0003<END\r\n\
+                 #0300\r\n<FL+0304<ML+0003<IT+0002<END\r\n\
+                 #0301\r\n<FL+0304<ML+0005<END\r\n",
+            )],
+        );
+        let collectibles = collectibles(&dir.files(), &NameTable::default());
+        assert_eq!(collectibles.len(), 2);
+        let missiles = &collectibles[0];
+        assert_eq!((missiles.event, missiles.flag), (Some(200), 1551));
+        assert!(
+            missiles.pickups
+                == [
+                    Pickup::Weapon { weapon: 5, ammo: 5 },
+                    Pickup::Weapon {
+                        weapon: 10,
+                        ammo: 3
+                    }
+                ]
+        );
+        let capsule = &collectibles[1];
+        assert_eq!(capsule.pickups, [Pickup::Life(3)]);
+    }
+
+    #[test]
+    fn boss_fights_take_vanilla_names() {
+        let dir = TestDir::new(
+            "bosses",
+            &[(
+                "Eggs.tsc",
+                "#0400\r\n<BSL0401<END\r\n\
+                 #0401\r\n<FL+0103<FL+0104<END\r\n\
+                 #0500\r\n<BSL0501<END\r\n\
+                 #0501\r\n<FL+0500<END\r\n",
+            )],
+        );
+        let fights = boss_fights(&dir.files(), &NameTable::default());
+        assert_eq!(fights.len(), 2);
+        let igor = &fights[0];
+        assert_eq!((igor.name, igor.events), (Some("Igor"), Some((400, 401))));
+        assert_eq!((igor.flag, igor.story_flags.as_slice()), (103, &[104][..]));
+        assert_eq!(fights[1].name, None);
+    }
+}
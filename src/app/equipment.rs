@@ -0,0 +1,147 @@
+use cavestory_save::{
+    items::{Equip, EquipOpt, Equipment, Weapon},
+    strum::IntoEnumIterator,
+};
+use egui::{DragValue, Ui};
+
+use super::utils::raw::{EquipBits, GameId, Stars, EQUIPMENT_BITS};
+
+// bits of the named equipment, in `Equipment` order
+const BOOSTER_08: usize = 0;
+const TURBOCHARGE: usize = 3;
+const BOOSTER_20: usize = 5;
+const WHIMSICAL_STAR: usize = 7;
+
+const MACHINE_GUN: i32 = 4;
+const MAX_STARS: i16 = 3;
+
+/// An equipment combination the game doesn't expect.
+#[derive(Clone, Copy)]
+enum Conflict {
+    BothBoosters,
+    TurbochargeWithoutMachineGun,
+    StarWithoutCount,
+}
+
+impl Conflict {
+    fn find(bits: EquipBits, stars: Stars, machine_gun: bool) -> Vec<Self> {
+        let mut conflicts = Vec::new();
+        if bits.get(BOOSTER_08) && bits.get(BOOSTER_20) {
+            conflicts.push(Self::BothBoosters);
+        }
+        if bits.get(TURBOCHARGE) && !machine_gun {
+            conflicts.push(Self::TurbochargeWithoutMachineGun);
+        }
+        if bits.get(WHIMSICAL_STAR) && stars.0 <= 0 {
+            conflicts.push(Self::StarWithoutCount);
+        }
+        conflicts
+    }
+
+    fn warning(self) -> &'static str {
+        match self {
+            Self::BothBoosters => "Booster 0.8 and 2.0 are both equipped",
+            Self::TurbochargeWithoutMachineGun => "Turbocharge without a Machine Gun",
+            Self::StarWithoutCount => "Whimsical Star without any star",
+        }
+    }
+
+    fn fix(self) -> &'static str {
+        match self {
+            Self::BothBoosters => "Keep 2.0",
+            Self::TurbochargeWithoutMachineGun => "Unequip Turbocharge",
+            Self::StarWithoutCount => "Set 3 stars",
+        }
+    }
+}
+
+/// Whether a Machine Gun is among the used weapon slots.
+pub fn has_machine_gun(weapon: &[Weapon], unknown: &[Option<i32>]) -> bool {
+    weapon
+        .iter()
+        .zip(unknown)
+        .any(|(w, unknown)| unknown.unwrap_or(w.classification.id()) == MACHINE_GUN)
+}
+
+/// Set a named bit, keeping `GameProfile` in sync.
+fn switch(equipment: &mut Equip, bits: &mut EquipBits, bit: usize, value: bool) {
+    if let Some(equip) = Equipment::iter().nth(bit) {
+        bits.set(bit, value);
+        equipment.switch(equip, value);
+    }
+}
+
+/// Checkboxes for every bit of the equipment field, named or not.
+///
+/// `GameProfile` is only touched when a named bit is toggled; the raw bits
+/// are written back on save, so bits it doesn't model survive.
+pub fn draw_window(
+    ui: &mut Ui,
+    equipment: &mut Equip,
+    bits: &mut EquipBits,
+    stars: &mut Stars,
+    machine_gun: bool,
+) {
+    ui.label(format!("Raw: 0x{:08X}", bits.0));
+    ui.separator();
+
+    for (i, equip) in Equipment::iter().enumerate() {
+        let mut checked = bits.get(i);
+        ui.horizontal(|ui| {
+            if ui.checkbox(&mut checked, equip.to_string()).changed() {
+                switch(equipment, bits, i, checked);
+            }
+            if i == WHIMSICAL_STAR {
+                // a count out of range is shown as it is, only edits are clamped
+                ui.add(
+                    DragValue::new(&mut stars.0)
+                        .range(0..=MAX_STARS)
+                        .clamp_existing_to_range(false),
+                )
+                .on_hover_text("Stars");
+            }
+        });
+    }
+
+    let conflicts = Conflict::find(*bits, *stars, machine_gun);
+    if !conflicts.is_empty() {
+        ui.separator();
+    }
+    for conflict in conflicts {
+        ui.horizontal(|ui| {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                format!("⚠ {}", conflict.warning()),
+            );
+            if ui.small_button(conflict.fix()).clicked() {
+                match conflict {
+                    Conflict::BothBoosters => switch(equipment, bits, BOOSTER_08, false),
+                    Conflict::TurbochargeWithoutMachineGun => {
+                        switch(equipment, bits, TURBOCHARGE, false)
+                    }
+                    Conflict::StarWithoutCount => stars.0 = MAX_STARS,
+                }
+            }
+        });
+    }
+
+    let named = Equipment::iter().count();
+    egui::CollapsingHeader::new("Other bits")
+        .default_open(bits.0 >> named != 0)
+        .show(ui, |ui| {
+            egui::Grid::new("equipment-bits").show(ui, |ui| {
+                for bit in named..EQUIPMENT_BITS {
+                    let mut checked = bits.get(bit);
+                    if ui
+                        .checkbox(&mut checked, format!("0x{:X}", 1u32 << bit))
+                        .changed()
+                    {
+                        bits.set(bit, checked);
+                    }
+                    if (bit - named) % 4 == 3 {
+                        ui.end_row();
+                    }
+                }
+            });
+        });
+}
@@ -0,0 +1,87 @@
+use egui::{Context, DragValue, RichText, ScrollArea};
+
+use super::data::xref::FlagIndex;
+use super::utils::raw::{Flags, FLAG_COUNT};
+
+#[derive(Default)]
+pub struct FlagWindow {
+    pub open: bool,
+    /// The flag to go to, highlighted in the list.
+    target: usize,
+    scroll: bool,
+    /// Only list flags some script uses.
+    used_only: bool,
+}
+
+impl FlagWindow {
+    /// Open the window at `flag`.
+    pub fn jump(&mut self, flag: usize) {
+        self.open = true;
+        self.target = flag.min(FLAG_COUNT - 1);
+        self.scroll = true;
+    }
+
+    pub fn show(&mut self, ctx: &Context, flags: &mut Flags, index: Option<&FlagIndex>) {
+        let mut open = self.open;
+        egui::Window::new("Flags")
+            .open(&mut open)
+            .default_height(400.)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("go to");
+                    if ui
+                        .add(DragValue::new(&mut self.target).range(0..=FLAG_COUNT - 1))
+                        .changed()
+                    {
+                        self.scroll = true;
+                    }
+                    if let Some(index) = index {
+                        if ui
+                            .checkbox(&mut self.used_only, "used by scripts only")
+                            .changed()
+                        {
+                            self.scroll = true;
+                        }
+                        egui::CollapsingHeader::new("where used")
+                            .id_salt("flag-where-used")
+                            .show(ui, |ui| ui.label(index.describe(self.target)));
+                    }
+                });
+                ui.separator();
+
+                let rows: Vec<usize> = match index {
+                    Some(index) if self.used_only => index.flags().collect(),
+                    _ => (0..FLAG_COUNT).collect(),
+                };
+                let row_height = ui.spacing().interact_size.y;
+                let mut area = ScrollArea::vertical().auto_shrink(false);
+                if std::mem::take(&mut self.scroll) {
+                    let row = rows.partition_point(|&id| id < self.target);
+                    let offset = row as f32 * (row_height + ui.spacing().item_spacing.y);
+                    area = area.vertical_scroll_offset(offset);
+                }
+                area.show_rows(ui, row_height, rows.len(), |ui, range| {
+                    for &id in &rows[range] {
+                        let mut value = flags.get(id);
+                        let mut label = RichText::new(format!("{id:04}")).monospace();
+                        if id == self.target {
+                            label = label.strong().color(ui.visuals().selection.stroke.color);
+                        }
+                        let mut response = ui.checkbox(&mut value, label);
+                        if let Some(index) = index {
+                            response = response.on_hover_ui(|ui| {
+                                ui.label(index.describe(id));
+                            });
+                        }
+                        if response.changed() {
+                            flags.set(id, value);
+                        }
+                        if response.secondary_clicked() {
+                            self.target = id;
+                        }
+                    }
+                });
+            });
+        self.open = open;
+    }
+}
@@ -0,0 +1,87 @@
+use std::path::PathBuf;
+
+use egui::{ColorImage, Context, Image, Rect, TextureHandle, TextureOptions, Vec2};
+
+use super::data::GameData;
+
+/// A sprite sheet of icons, one per id.
+pub struct Sheet {
+    texture: TextureHandle,
+    /// Size of an icon, in texture pixels.
+    cell: Vec2,
+    /// Icons per row of the sheet.
+    columns: usize,
+    /// Icons per row of a picker.
+    pub picker_columns: usize,
+}
+
+impl Sheet {
+    /// Slice a sheet of `width` × `height` icons, `columns` per row, at the freeware's resolution.
+    fn load(
+        ctx: &Context,
+        data: &GameData,
+        name: &str,
+        [width, height]: [usize; 2],
+        columns: Option<usize>,
+        picker_columns: usize,
+    ) -> Option<Self> {
+        let image = data.image(name)?;
+        // Cave Story+ sheets are twice as large
+        let scale = match columns {
+            Some(columns) => image.width / (columns * width),
+            None => image.height / height,
+        }
+        .clamp(1, 2);
+        let cell = [width * scale, height * scale];
+        let columns = columns.unwrap_or(image.width / cell[0]).max(1);
+        let texture = ctx.load_texture(
+            name,
+            ColorImage::from_rgba_unmultiplied([image.width, image.height], &image.rgba),
+            TextureOptions::NEAREST,
+        );
+        Some(Self {
+            texture,
+            cell: Vec2::new(cell[0] as f32, cell[1] as f32),
+            columns,
+            picker_columns,
+        })
+    }
+
+    /// The icon of `id`, twice the size of the freeware's, if the sheet has one.
+    pub fn image(&self, id: i32) -> Option<Image<'static>> {
+        let id = usize::try_from(id).ok()?;
+        let texture_size = self.texture.size_vec2();
+        let min = Vec2::new(
+            (id % self.columns) as f32 * self.cell.x,
+            (id / self.columns) as f32 * self.cell.y,
+        );
+        if min.x + self.cell.x > texture_size.x || min.y + self.cell.y > texture_size.y {
+            return None;
+        }
+        let uv = Rect::from_min_size((min / texture_size).to_pos2(), self.cell / texture_size);
+        let size = self.cell / self.cell.y * 32.;
+        Some(Image::new((self.texture.id(), size)).uv(uv))
+    }
+}
+
+/// Weapon and item icons of the data folder.
+#[derive(Default)]
+pub struct Icons {
+    root: Option<PathBuf>,
+    pub weapons: Option<Sheet>,
+    pub items: Option<Sheet>,
+}
+
+impl Icons {
+    pub fn update(&mut self, ctx: &Context, data: Option<&GameData>) {
+        let root = data.map(|data| &data.root);
+        if self.root.as_ref() == root {
+            return;
+        }
+        self.root = root.cloned();
+        // ArmsImage is a single row of 16 × 16 icons, ItemImage has 8 columns of 32 × 16 icons
+        self.weapons = data.and_then(|data| Sheet::load(ctx, data, "ArmsImage", [16, 16], None, 6));
+        self.items =
+            data.and_then(|data| Sheet::load(ctx, data, "ItemImage", [32, 16], Some(8), 6));
+    }
+}
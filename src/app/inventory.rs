@@ -1,19 +1,33 @@
 use std::ops::{AddAssign, SubAssign};
 
-use cavestory_save::{items::Inventory, strum::IntoEnumIterator};
+use cavestory_save::items::Inventory;
 use egui::{
     text::{LayoutJob, TextWrapping},
     TextFormat, Ui, Vec2,
 };
 
-const MAX_INVENTORY_NUM: usize = 31;
+use super::data::NameTable;
+use super::icons::Sheet;
+use super::slots::{self, SlotAction};
+use super::utils::raw::GameId;
+use super::widgets::{icon_combo, id_combo, id_label, id_options, pick_id};
 
-pub fn draw_window(ui: &mut Ui, inventory_num: &mut usize, inventory: &mut [Inventory]) {
+pub const MAX_INVENTORY_NUM: usize = 31;
+
+pub fn draw_window(
+    ui: &mut Ui,
+    inventory_num: &mut usize,
+    inventory: &mut [Inventory],
+    unknown: &mut [Option<i32>],
+    names: Option<&NameTable>,
+    icons: Option<&Sheet>,
+) {
     ui.horizontal(|ui| {
         let could_add = (*inventory_num == 0
             || inventory
                 .get(*inventory_num - 1)
-                .is_some_and(|&i| i != Inventory::None))
+                .is_some_and(|&i| i != Inventory::None)
+            || unknown.get(*inventory_num - 1).is_some_and(Option::is_some))
             // do not set the 8th weapon, you may go into issues.
             && *inventory_num < MAX_INVENTORY_NUM;
         let could_sub = *inventory_num > 0;
@@ -25,6 +39,7 @@ pub fn draw_window(ui: &mut Ui, inventory_num: &mut usize, inventory: &mut [Inve
         if ui.button(" - ").clicked() && could_sub {
             inventory_num.sub_assign(1);
             inventory[*inventory_num] = Default::default();
+            unknown[*inventory_num] = None;
         }
 
         if ui.button(" x ").clicked() {
@@ -32,18 +47,21 @@ pub fn draw_window(ui: &mut Ui, inventory_num: &mut usize, inventory: &mut [Inve
             inventory[..]
                 .iter_mut()
                 .for_each(|i| *i = Default::default());
+            unknown.iter_mut().for_each(|i| *i = None);
         }
     });
 
     ui.separator();
 
+    let mut action = None;
     let chunk_size = 6;
-    for (chunk_i, chunk) in inventory[..*inventory_num]
+    for (chunk_i, (chunk, unknown_chunk)) in inventory[..*inventory_num]
         .chunks_mut(chunk_size)
+        .zip(unknown.chunks_mut(chunk_size))
         .enumerate()
     {
         ui.horizontal(|ui| {
-            for (i, inventory) in chunk.iter_mut().enumerate() {
+            for (i, (inventory, unknown)) in chunk.iter_mut().zip(unknown_chunk).enumerate() {
                 let pos = chunk_i * chunk_size + i;
                 let truncated_warp = TextWrapping {
                     max_rows: 1,
@@ -51,29 +69,95 @@ pub fn draw_window(ui: &mut Ui, inventory_num: &mut usize, inventory: &mut [Inve
                     ..Default::default()
                 };
                 let mut layout_job = LayoutJob::default();
-                layout_job.append(&inventory.to_string(), 0., TextFormat::default());
+                layout_job.append(
+                    &id_label(*inventory, *unknown, names),
+                    0.,
+                    TextFormat::default(),
+                );
                 layout_job.wrap = truncated_warp;
-                ui.scope(|ui| {
-                    ui.spacing_mut().icon_spacing = 0.;
-                    ui.spacing_mut().icon_width = 5.;
-                    ui.spacing_mut().combo_width = 115.;
-                    ui.spacing_mut().item_spacing = Vec2::from([3., 3.]);
-                    ui.set_min_width(120.);
-                    ui.set_max_width(120.);
-                    egui::ComboBox::new(format!("inventorytype-box-{pos}"), "")
-                        .selected_text(layout_job)
-                        .truncate()
-                        .show_ui(ui, |ui| {
-                            let mut iter = Inventory::iter();
-                            if pos + 1 < *inventory_num {
-                                iter.next();
-                            }
-                            for model in iter {
-                                ui.selectable_value(inventory, model, model.to_string());
+                let response = ui
+                    .scope(|ui| {
+                        ui.spacing_mut().icon_spacing = 0.;
+                        ui.spacing_mut().icon_width = 5.;
+                        ui.spacing_mut().combo_width = 115.;
+                        ui.spacing_mut().item_spacing = Vec2::from([3., 3.]);
+                        ui.set_min_width(120.);
+                        ui.set_max_width(120.);
+                        ui.vertical(|ui| {
+                            let can_insert = *inventory_num < MAX_INVENTORY_NUM;
+                            slots::handle(ui, "inventory", pos, can_insert, &mut action, |ui| {
+                                pick_id::<Inventory>(ui, ("insert-inventory", pos), names)
+                            });
+                            let skip_none = pos + 1 < *inventory_num
+                                && (*inventory != Inventory::None || unknown.is_some());
+                            let options = id_options::<Inventory>(ui.ctx(), names, skip_none);
+                            match icons {
+                                Some(sheet) => icon_combo(
+                                    ui,
+                                    format!("inventorytype-box-{pos}"),
+                                    115.,
+                                    inventory,
+                                    unknown,
+                                    &options,
+                                    sheet,
+                                ),
+                                None => id_combo(
+                                    ui,
+                                    format!("inventorytype-box-{pos}"),
+                                    layout_job,
+                                    115.,
+                                    inventory,
+                                    unknown,
+                                    &options,
+                                ),
                             }
                         });
-                });
+                    })
+                    .response;
+                slots::drop_target(ui, &response, "inventory", pos, &mut action);
             }
         });
     }
+
+    if let Some(action) = action {
+        action.apply(
+            &mut inventory[..MAX_INVENTORY_NUM],
+            &mut unknown[..MAX_INVENTORY_NUM],
+            inventory_num,
+            Inventory::with_unknown,
+        );
+    }
+}
+
+fn position(
+    inventory: &[Inventory],
+    unknown: &[Option<i32>],
+    num: usize,
+    id: i32,
+) -> Option<usize> {
+    (0..num).find(|&i| unknown[i].unwrap_or(inventory[i].id()) == id)
+}
+
+/// Add an item after the used slots, unless it's held already or the inventory is full.
+pub fn give(inventory: &mut [Inventory], unknown: &mut [Option<i32>], num: &mut usize, id: i32) {
+    if position(inventory, unknown, *num, id).is_none() && *num < MAX_INVENTORY_NUM {
+        SlotAction::Insert(*num, id).apply(
+            &mut inventory[..MAX_INVENTORY_NUM],
+            &mut unknown[..MAX_INVENTORY_NUM],
+            num,
+            Inventory::with_unknown,
+        );
+    }
+}
+
+/// Remove the first slot holding an item, if any.
+pub fn take(inventory: &mut [Inventory], unknown: &mut [Option<i32>], num: &mut usize, id: i32) {
+    if let Some(at) = position(inventory, unknown, *num, id) {
+        SlotAction::Remove(at).apply(
+            &mut inventory[..MAX_INVENTORY_NUM],
+            &mut unknown[..MAX_INVENTORY_NUM],
+            num,
+            Inventory::with_unknown,
+        );
+    }
 }
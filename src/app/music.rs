@@ -0,0 +1,178 @@
+use std::path::PathBuf;
+
+use egui::{Context, Ui};
+use kanal::{unbounded, Receiver, Sender};
+
+use super::data::{org, GameData};
+use super::storage::{Storage, StorageIO, WAV};
+
+struct Rendered {
+    root: PathBuf,
+    song: i32,
+    /// Interleaved stereo samples, or why the song couldn't be rendered.
+    pcm: Result<Vec<i16>, String>,
+}
+
+/// Renders the selected song from the data folder, to listen to or export.
+pub struct MusicPreview {
+    storage: Storage,
+    rendered: Option<Rendered>,
+    /// The song being rendered in background.
+    pending: Option<(PathBuf, i32)>,
+    sender: Sender<Rendered>,
+    recv: Receiver<Rendered>,
+    #[cfg(feature = "playback")]
+    player: Option<(rodio::OutputStream, rodio::Sink)>,
+}
+
+impl Default for MusicPreview {
+    fn default() -> Self {
+        let (sender, recv) = unbounded();
+        Self {
+            storage: Storage::new(&WAV),
+            rendered: None,
+            pending: None,
+            sender,
+            recv,
+            #[cfg(feature = "playback")]
+            player: None,
+        }
+    }
+}
+
+/// Read what rendering needs; the rendering itself takes a while.
+fn prepare(data: &GameData, song: i32) -> Result<(org::Organya, Vec<u8>), String> {
+    let waves = data
+        .wave_table()
+        .ok_or("no Organya wave table in the data folder")?;
+    let song = data
+        .song(song)
+        .ok_or("this song's .org file was not found in the data folder")?;
+    let song = org::parse(&song).map_err(|e| e.to_string())?;
+    Ok((song, waves))
+}
+
+fn render(song: &org::Organya, waves: &[u8]) -> Vec<i16> {
+    // play the loop twice, as the game would before a scene changes
+    org::render(song, waves, 1)
+}
+
+impl MusicPreview {
+    /// Render `song` in background, sending it to [`Self::draw`] once done.
+    fn start(&mut self, ctx: &Context, data: &GameData, song: i32) {
+        let root = data.root.clone();
+        let (song_data, waves) = match prepare(data, song) {
+            Ok(prepared) => prepared,
+            Err(e) => {
+                self.rendered = Some(Rendered {
+                    root,
+                    song,
+                    pcm: Err(e),
+                });
+                return;
+            }
+        };
+        self.pending = Some((root.clone(), song));
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let sender = self.sender.clone();
+            let ctx = ctx.clone();
+            let future = async move {
+                let pcm = compio::runtime::spawn_blocking(move || render(&song_data, &waves))
+                    .await
+                    .map_err(|_| "rendering failed".to_owned());
+                let _ = sender.as_async().send(Rendered { root, song, pcm }).await;
+                ctx.request_repaint();
+            };
+            let _ = crate::TASK_SENDER.get().unwrap().send(Box::pin(future));
+        }
+        // no data folders on the web, so no songs to render either
+        #[cfg(target_arch = "wasm32")]
+        let _ = (ctx, song_data, waves);
+    }
+
+    pub fn draw(&mut self, ui: &mut Ui, data: Option<&GameData>, song: i32) {
+        let Some(data) = data else {
+            return;
+        };
+        while let Ok(Some(rendered)) = self.recv.try_recv() {
+            if self
+                .pending
+                .as_ref()
+                .is_some_and(|(root, id)| *root == rendered.root && *id == rendered.song)
+            {
+                self.pending = None;
+                self.rendered = Some(rendered);
+            }
+        }
+        let outdated = self
+            .rendered
+            .as_ref()
+            .is_none_or(|r| r.song != song || r.root != data.root);
+
+        ui.horizontal(|ui| {
+            if outdated {
+                #[cfg(feature = "playback")]
+                {
+                    self.player = None;
+                }
+                let rendering = self
+                    .pending
+                    .as_ref()
+                    .is_some_and(|(root, id)| *root == data.root && *id == song);
+                if rendering {
+                    ui.spinner();
+                    ui.label("rendering…");
+                } else if ui
+                    .button("Render preview")
+                    .on_hover_text("drums are approximated with noise, not the game's samples")
+                    .clicked()
+                {
+                    self.start(ui.ctx(), data, song);
+                }
+                return;
+            }
+            let Some(rendered) = &self.rendered else {
+                return;
+            };
+            match &rendered.pcm {
+                Ok(pcm) => {
+                    let seconds = pcm.len() / 2 / org::SAMPLE_RATE as usize;
+                    ui.label(format!("{}:{:02}", seconds / 60, seconds % 60))
+                        .on_hover_text("drums are approximated with noise, not the game's samples");
+                    #[cfg(feature = "playback")]
+                    playback(ui, &mut self.player, pcm);
+                    if ui.button("Export WAV").clicked() {
+                        self.storage.try_write_data(&org::encode_wav(pcm));
+                    }
+                }
+                Err(e) => {
+                    ui.colored_label(ui.visuals().warn_fg_color, format!("⚠ {e}"));
+                }
+            }
+        });
+    }
+}
+
+#[cfg(feature = "playback")]
+fn playback(ui: &mut Ui, player: &mut Option<(rodio::OutputStream, rodio::Sink)>, pcm: &[i16]) {
+    let playing = player.as_ref().is_some_and(|(_, sink)| !sink.empty());
+    if playing {
+        if ui.button("■ Stop").clicked() {
+            *player = None;
+        }
+    } else if ui.button("▶ Play").clicked() {
+        *player = rodio::OutputStream::try_default()
+            .ok()
+            .and_then(|(stream, handle)| {
+                let sink = rodio::Sink::try_new(&handle).ok()?;
+                sink.append(rodio::buffer::SamplesBuffer::new(
+                    2,
+                    org::SAMPLE_RATE,
+                    pcm.to_vec(),
+                ));
+                Some((stream, sink))
+            });
+    }
+}
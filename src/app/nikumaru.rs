@@ -0,0 +1,246 @@
+//! `290.rec`, the Nikumaru counter's best time on the Sacred Grounds.
+//!
+//! The time is stored four times, each copy obfuscated with its own random byte.
+
+use std::fmt;
+
+use egui::{Context, DragValue, Ui};
+
+use super::storage::{Storage, StorageIO, NIKUMARU};
+use super::utils::load_error;
+
+const RECORD_SIZE: usize = 0x14;
+/// The counter ticks at 50 frames per second.
+const FPS: u32 = 50;
+/// The game stops counting at 100 minutes.
+const MAX_TIME: u32 = 300_000;
+
+#[derive(Debug)]
+pub enum RecordError {
+    InvalidSize(usize),
+    Mismatch,
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordError::InvalidSize(size) => {
+                write!(f, "expected {RECORD_SIZE} bytes, found {size} bytes")
+            }
+            RecordError::Mismatch => write!(f, "the stored copies of the time don't match"),
+        }
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+#[derive(Clone, Copy)]
+pub struct Record {
+    /// Best time, in frames.
+    pub time: u32,
+    keys: [u8; 4],
+}
+
+impl Record {
+    pub fn decode(data: &[u8]) -> Result<Self, RecordError> {
+        if data.len() != RECORD_SIZE {
+            return Err(RecordError::InvalidSize(data.len()));
+        }
+
+        let mut keys = [0; 4];
+        keys.copy_from_slice(&data[0x10..0x14]);
+
+        let mut counters = [0u32; 4];
+        for (i, counter) in counters.iter_mut().enumerate() {
+            let key = keys[i];
+            let mut bytes = [0; 4];
+            bytes.copy_from_slice(&data[i * 4..i * 4 + 4]);
+            bytes[0] = bytes[0].wrapping_sub(key);
+            bytes[1] = bytes[1].wrapping_sub(key);
+            bytes[2] = bytes[2].wrapping_sub(key);
+            bytes[3] = bytes[3].wrapping_sub(key / 2);
+            *counter = u32::from_le_bytes(bytes);
+        }
+
+        // the game only compares the first three copies
+        if counters[0] != counters[1] || counters[0] != counters[2] {
+            return Err(RecordError::Mismatch);
+        }
+
+        Ok(Self {
+            time: counters[0],
+            keys,
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(RECORD_SIZE);
+        for key in self.keys {
+            let mut bytes = self.time.to_le_bytes();
+            bytes[0] = bytes[0].wrapping_add(key);
+            bytes[1] = bytes[1].wrapping_add(key);
+            bytes[2] = bytes[2].wrapping_add(key);
+            bytes[3] = bytes[3].wrapping_add(key / 2);
+            data.extend_from_slice(&bytes);
+        }
+        data.extend_from_slice(&self.keys);
+        data
+    }
+}
+
+pub struct NikumaruEditor {
+    storage: Storage,
+    record: Option<Record>,
+}
+
+impl Default for NikumaruEditor {
+    fn default() -> Self {
+        Self {
+            storage: Storage::new(&NIKUMARU),
+            record: None,
+        }
+    }
+}
+
+impl NikumaruEditor {
+    pub fn update(&mut self, ctx: &Context) {
+        self.storage.drag_handle(ctx);
+        if let Some(data) = self.storage.try_read_data() {
+            match Record::decode(&data) {
+                Ok(record) => self.record = Some(record),
+                Err(e) => load_error(e.to_string()),
+            }
+        }
+    }
+
+    pub fn file_ops(&mut self, ui: &mut Ui) {
+        if ui.button("Open 290.rec").clicked() {
+            self.storage.open_dialog();
+        }
+    }
+
+    pub fn show(&mut self, ctx: &Context) {
+        let Some(record) = &mut self.record else {
+            return;
+        };
+        let mut open = true;
+        egui::Window::new("Nikumaru Counter")
+            .open(&mut open)
+            .show(ctx, |ui| {
+                draw_window(ui, record);
+                ui.separator();
+                if ui.button("Save").clicked() {
+                    self.storage.try_write_data(&record.encode());
+                }
+            });
+        if !open {
+            self.record = None;
+        }
+    }
+}
+
+/// Split a time into minutes, seconds, tenths and the frames below a tenth.
+fn split_time(time: u32) -> (u32, u32, u32, u32) {
+    (
+        time / (FPS * 60),
+        time / FPS % 60,
+        time / (FPS / 10) % 10,
+        time % (FPS / 10),
+    )
+}
+
+fn join_time(minutes: u32, seconds: u32, tenths: u32, frames: u32) -> u32 {
+    ((minutes * 60 + seconds) * FPS + tenths * (FPS / 10) + frames).min(MAX_TIME)
+}
+
+fn draw_window(ui: &mut Ui, record: &mut Record) {
+    // frames below a tenth of second are kept unless the raw value is edited
+    let (mut minutes, mut seconds, mut tenths, frames) = split_time(record.time.min(MAX_TIME));
+
+    ui.horizontal(|ui| {
+        let mut changed = false;
+        changed |= ui
+            .add(DragValue::new(&mut minutes).range(0..=MAX_TIME / (FPS * 60)))
+            .changed();
+        ui.label(":");
+        changed |= ui.add(DragValue::new(&mut seconds).range(0..=59)).changed();
+        ui.label(".");
+        changed |= ui.add(DragValue::new(&mut tenths).range(0..=9)).changed();
+        if changed {
+            record.time = join_time(minutes, seconds, tenths, frames);
+        }
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("frames");
+        ui.add(
+            DragValue::new(&mut record.time)
+                .range(0..=MAX_TIME)
+                .clamp_existing_to_range(false),
+        );
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let record = Record {
+            time: 3 * 60 * FPS + 1234,
+            keys: [0x00, 0x7F, 0xC3, 0xFF],
+        };
+        let data = record.encode();
+        assert_eq!(data.len(), RECORD_SIZE);
+        assert_eq!(&data[0x10..], &record.keys);
+
+        let decoded = Record::decode(&data).unwrap();
+        assert_eq!(decoded.time, record.time);
+        assert_eq!(decoded.encode(), data);
+    }
+
+    #[test]
+    fn obfuscation() {
+        let record = Record {
+            time: 0x0102_0304,
+            keys: [0x10, 0, 0, 0],
+        };
+        let data = record.encode();
+        // the high byte only gets half the key
+        assert_eq!(&data[..4], &[0x14, 0x13, 0x12, 0x09]);
+        assert_eq!(&data[4..8], &[0x04, 0x03, 0x02, 0x01]);
+    }
+
+    #[test]
+    fn only_three_copies_compared() {
+        let record = Record {
+            time: MAX_TIME,
+            keys: [1, 2, 3, 4],
+        };
+        let mut data = record.encode();
+        data[12] ^= 0xFF;
+        assert_eq!(Record::decode(&data).unwrap().time, MAX_TIME);
+
+        data[4] ^= 0xFF;
+        assert!(matches!(Record::decode(&data), Err(RecordError::Mismatch)));
+    }
+
+    #[test]
+    fn hundred_minutes() {
+        assert_eq!(split_time(MAX_TIME), (100, 0, 0, 0));
+        assert_eq!(join_time(100, 0, 0, 0), MAX_TIME);
+        // editing the seconds of a maxed out record doesn't go past the limit
+        assert_eq!(join_time(100, 30, 0, 0), MAX_TIME);
+        let time = 99 * 60 * FPS + 59 * FPS + 9 * (FPS / 10) + 3;
+        assert_eq!(split_time(time), (99, 59, 9, 3));
+    }
+
+    #[test]
+    fn invalid_size() {
+        assert!(matches!(
+            Record::decode(&[0; RECORD_SIZE - 1]),
+            Err(RecordError::InvalidSize(0x13))
+        ));
+    }
+}
@@ -0,0 +1,205 @@
+//! Searchable list of id options, shown inside the id combo boxes.
+
+use std::any::type_name;
+
+use egui::{Id, Key, Response, ScrollArea, TextEdit, Ui};
+
+use super::utils::raw::GameId;
+use super::widgets::IdOption;
+
+/// Recent choices kept per id type, across sessions.
+const MAX_RECENT: usize = 5;
+
+#[derive(Clone, Default)]
+struct State {
+    query: String,
+    highlighted: usize,
+}
+
+/// Score of `text` for a fuzzy `query`, or `None` if the query's characters
+/// don't all appear in order. Consecutive and word-start matches score higher.
+fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut at = 0;
+    let mut previous = None;
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = at + text[at..].iter().position(|&c| c == q)?;
+        score += 1;
+        if previous.is_some_and(|p| p + 1 == found) {
+            score += 4;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        previous = Some(found);
+        at = found + 1;
+    }
+    Some(score - text.len() as i32 / 8)
+}
+
+/// Options matching `query` best first; recent choices first without a query.
+fn matches<'a>(query: &str, options: &'a [IdOption], recent: &[i32]) -> Vec<&'a IdOption> {
+    let query = query.trim();
+    if query.is_empty() {
+        let recent_options = recent
+            .iter()
+            .filter_map(|&id| options.iter().find(|o| o.id == id));
+        let others = options.iter().filter(|o| !recent.contains(&o.id));
+        return recent_options.chain(others).collect();
+    }
+
+    let id = query.trim_start_matches('#').parse::<i32>().ok();
+    let mut scored: Vec<_> = options
+        .iter()
+        .filter_map(|o| {
+            let score = if id == Some(o.id) {
+                Some(i32::MAX)
+            } else {
+                fuzzy_score(query, &o.name)
+            };
+            score.map(|s| (s, o))
+        })
+        .collect();
+    scored.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+    scored.into_iter().map(|(_, o)| o).collect()
+}
+
+/// Filter box over `options` laid out in `columns`, with arrow keys and Enter
+/// to pick. Returns the picked id and closes the surrounding popup.
+pub fn show<T: GameId>(
+    ui: &mut Ui,
+    id: Id,
+    current: i32,
+    options: &[IdOption],
+    columns: usize,
+    mut item: impl FnMut(&mut Ui, &IdOption, bool) -> Response,
+) -> Option<i32> {
+    let recent_id = Id::new(("picker-recent", type_name::<T>()));
+    let mut state: State = ui.data(|d| d.get_temp(id)).unwrap_or_default();
+    let recent: Vec<i32> = ui
+        .data_mut(|d| d.get_persisted(recent_id))
+        .unwrap_or_default();
+
+    let edit = ui.add(
+        TextEdit::singleline(&mut state.query)
+            .hint_text("Search…")
+            .desired_width(f32::INFINITY),
+    );
+    edit.request_focus();
+    if edit.changed() {
+        state.highlighted = 0;
+    }
+
+    let found = matches(&state.query, options, &recent);
+    let columns = columns.max(1);
+    let (step, enter) = ui.input(|i| {
+        let key = |key| isize::from(i.key_pressed(key));
+        let mut step = (key(Key::ArrowDown) - key(Key::ArrowUp)) * columns as isize;
+        if columns > 1 {
+            step += key(Key::ArrowRight) - key(Key::ArrowLeft);
+        }
+        (step, i.key_pressed(Key::Enter))
+    });
+    state.highlighted = state
+        .highlighted
+        .saturating_add_signed(step)
+        .min(found.len().saturating_sub(1));
+
+    let mut picked = enter
+        .then(|| found.get(state.highlighted).map(|o| o.id))
+        .flatten();
+    ScrollArea::vertical().max_height(300.).show(ui, |ui| {
+        egui::Grid::new(id.with("grid")).show(ui, |ui| {
+            for (i, option) in found.iter().enumerate() {
+                let mut response = item(ui, option, current == option.id);
+                if i == state.highlighted {
+                    response = response.highlight();
+                    if step != 0 {
+                        response.scroll_to_me(None);
+                    }
+                }
+                if response.clicked() {
+                    picked = Some(option.id);
+                }
+                if (i + 1) % columns == 0 {
+                    ui.end_row();
+                }
+            }
+        });
+    });
+
+    if let Some(picked) = picked {
+        let mut recent = recent;
+        recent.retain(|&id| id != picked);
+        recent.insert(0, picked);
+        recent.truncate(MAX_RECENT);
+        ui.data_mut(|d| d.insert_persisted(recent_id, recent));
+        state = State::default();
+        ui.close();
+    }
+    ui.data_mut(|d| d.insert_temp(id, state));
+    picked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(names: &[(i32, &str)]) -> Vec<IdOption> {
+        names
+            .iter()
+            .map(|&(id, name)| IdOption {
+                id,
+                name: name.to_owned(),
+                details: None,
+            })
+            .collect()
+    }
+
+    fn ids(found: Vec<&IdOption>) -> Vec<i32> {
+        found.into_iter().map(|o| o.id).collect()
+    }
+
+    #[test]
+    fn fuzzy_score_order() {
+        assert_eq!(fuzzy_score("xyz", "Polar Star"), None);
+        assert_eq!(fuzzy_score("rats", "Polar Star"), None);
+        assert!(fuzzy_score("", "Polar Star").is_some());
+        // consecutive beats scattered, word starts beat the middle of a word
+        assert!(fuzzy_score("star", "Polar Star") > fuzzy_score("star", "Spur Tail Arms Rack"));
+        assert!(fuzzy_score("ps", "Polar Star") > fuzzy_score("ps", "Capsule"));
+        assert_eq!(
+            fuzzy_score("POLAR", "polar star"),
+            fuzzy_score("polar", "Polar Star")
+        );
+    }
+
+    #[test]
+    fn matches_best_first() {
+        let options = options(&[
+            (1, "Snake"),
+            (2, "Polar Star"),
+            (13, "Spur"),
+            (7, "Bubbler"),
+        ]);
+        assert_eq!(ids(matches("pr", &options, &[])), [2, 13]);
+        assert_eq!(ids(matches("  ", &options, &[])), [1, 2, 13, 7]);
+        assert!(matches("zz", &options, &[]).is_empty());
+    }
+
+    #[test]
+    fn matches_id() {
+        let options = options(&[(1, "Snake"), (7, "Bubbler"), (13, "Spur")]);
+        assert_eq!(ids(matches("#13", &options, &[])), [13]);
+        assert_eq!(ids(matches("7", &options, &[])), [7]);
+        assert!(matches("#42", &options, &[]).is_empty());
+    }
+
+    #[test]
+    fn recent_first_without_query() {
+        let options = options(&[(1, "Snake"), (2, "Polar Star"), (13, "Spur")]);
+        assert_eq!(ids(matches("", &options, &[13, 42, 2])), [13, 2, 1]);
+        assert_eq!(ids(matches("snake", &options, &[13])), [1]);
+    }
+}
@@ -0,0 +1,190 @@
+use std::path::PathBuf;
+
+use cavestory_save::items::Position;
+use egui::{
+    Color32, ColorImage, Context, Image, Pos2, Sense, Slider, Stroke, TextureHandle,
+    TextureOptions, Vec2,
+};
+
+use super::data::{
+    pxm::{tile_kind, TileKind},
+    GameData, StageMap,
+};
+
+/// Pixels per tile, in game units.
+pub const TILE_SIZE: i32 = 16;
+/// `Position` units per pixel. The profile keeps the sub-pixel coordinate
+/// without its low byte, so a unit is 0x100 sub-pixels, half a pixel.
+pub const PIXEL_UNITS: i32 = 2;
+
+/// Position of the center of a tile, if a position can hold it.
+pub fn tile_center(tile: i32) -> Option<i16> {
+    i16::try_from((tile * TILE_SIZE + TILE_SIZE / 2) * PIXEL_UNITS).ok()
+}
+
+struct Rendered {
+    root: PathBuf,
+    map: i32,
+    /// The map and its size in tiles, `None` if its files are missing.
+    texture: Option<(TextureHandle, [usize; 2])>,
+}
+
+pub struct MapPreview {
+    pub open: bool,
+    zoom: f32,
+    rendered: Option<Rendered>,
+}
+
+impl Default for MapPreview {
+    fn default() -> Self {
+        Self {
+            open: false,
+            zoom: 1.,
+            rendered: None,
+        }
+    }
+}
+
+fn kind_color(kind: TileKind) -> Color32 {
+    match kind {
+        TileKind::Air => Color32::TRANSPARENT,
+        TileKind::Solid => Color32::from_gray(140),
+        TileKind::Slope => Color32::from_gray(100),
+        TileKind::Hurt => Color32::from_rgb(200, 60, 60),
+        TileKind::Water => Color32::from_rgb(60, 90, 200),
+    }
+}
+
+/// Draw the stage with its tileset, or with tile attributes if the tileset can't be decoded.
+fn render(stage: &StageMap) -> ColorImage {
+    let tiles = &stage.tiles;
+    match &stage.tileset {
+        Some(tileset) => {
+            // Cave Story+ doubles the resolution of tilesets, which are always 16 tiles wide
+            let size = (tileset.width / 16).max(1);
+            let width = tiles.width * size;
+            let mut rgba = vec![0; width * tiles.height * size * 4];
+            for y in 0..tiles.height {
+                for x in 0..tiles.width {
+                    let tile = tiles.tile(x, y) as usize;
+                    let (src_x, src_y) = (tile % 16 * size, tile / 16 * size);
+                    for row in 0..size {
+                        if src_y + row >= tileset.height {
+                            break;
+                        }
+                        let src = ((src_y + row) * tileset.width + src_x) * 4;
+                        let dst = ((y * size + row) * width + x * size) * 4;
+                        rgba[dst..dst + size * 4]
+                            .copy_from_slice(&tileset.rgba[src..src + size * 4]);
+                    }
+                }
+            }
+            ColorImage::from_rgba_unmultiplied([width, tiles.height * size], &rgba)
+        }
+        None => {
+            let rgba: Vec<u8> = tiles
+                .tiles
+                .iter()
+                .flat_map(|&tile| {
+                    let attribute = stage
+                        .attributes
+                        .as_ref()
+                        .and_then(|a| a.get(tile as usize))
+                        .copied()
+                        .unwrap_or_default();
+                    kind_color(tile_kind(attribute)).to_array()
+                })
+                .collect();
+            ColorImage::from_rgba_premultiplied([tiles.width, tiles.height], &rgba)
+        }
+    }
+}
+
+impl MapPreview {
+    pub fn show(
+        &mut self,
+        ctx: &Context,
+        data: Option<&GameData>,
+        map: i32,
+        position: &mut Position,
+    ) {
+        let mut open = self.open;
+        egui::Window::new("Map Preview")
+            .open(&mut open)
+            .default_size([480., 320.])
+            .show(ctx, |ui| {
+                let Some(data) = data else {
+                    ui.label("Choose a game data folder in the Data menu to preview maps.");
+                    return;
+                };
+
+                let outdated = self
+                    .rendered
+                    .as_ref()
+                    .is_none_or(|r| r.map != map || r.root != data.root);
+                if outdated {
+                    self.rendered = Some(Rendered {
+                        root: data.root.clone(),
+                        map,
+                        texture: data.stage_map(map).map(|stage| {
+                            let texture = ctx.load_texture(
+                                "map-preview",
+                                render(&stage),
+                                TextureOptions::NEAREST,
+                            );
+                            (texture, [stage.tiles.width, stage.tiles.height])
+                        }),
+                    });
+                }
+                let Some((texture, tiles)) =
+                    self.rendered.as_ref().and_then(|r| r.texture.as_ref())
+                else {
+                    ui.label("This map's files were not found in the data folder.");
+                    return;
+                };
+
+                ui.horizontal(|ui| {
+                    ui.label("zoom");
+                    ui.add(Slider::new(&mut self.zoom, 0.25..=4.).logarithmic(true));
+                    ui.label("click a tile to move the player there");
+                });
+
+                let scale = self.zoom;
+                let size = Vec2::new(
+                    (tiles[0] as i32 * TILE_SIZE) as f32,
+                    (tiles[1] as i32 * TILE_SIZE) as f32,
+                ) * scale;
+
+                egui::ScrollArea::both().show(ui, |ui| {
+                    let response = ui.add(Image::new((texture.id(), size)).sense(Sense::click()));
+
+                    if let Some(pointer) = response
+                        .interact_pointer_pos()
+                        .filter(|_| response.clicked())
+                    {
+                        let pixel = (pointer - response.rect.min) / scale;
+                        let tile_x = pixel.x as i32 / TILE_SIZE;
+                        let tile_y = pixel.y as i32 / TILE_SIZE;
+                        if let (Some(x), Some(y)) = (tile_center(tile_x), tile_center(tile_y)) {
+                            position.x = x;
+                            position.y = y;
+                        }
+                    }
+
+                    let player = Pos2::new(
+                        position.x as f32 / PIXEL_UNITS as f32,
+                        position.y as f32 / PIXEL_UNITS as f32,
+                    );
+                    let center = response.rect.min + player.to_vec2() * scale;
+                    let painter = ui.painter_at(response.rect);
+                    painter.circle_stroke(
+                        center,
+                        TILE_SIZE as f32 / 2. * scale,
+                        Stroke::new(2., Color32::YELLOW),
+                    );
+                    painter.circle_filled(center, 2., Color32::RED);
+                });
+            });
+        self.open = open;
+    }
+}
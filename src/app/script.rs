@@ -0,0 +1,124 @@
+use std::path::PathBuf;
+
+use egui::{Color32, Context, RichText, ScrollArea, Ui};
+
+use super::data::{
+    tsc::{self, Token, FLAG_COMMANDS},
+    xref::FlagIndex,
+    GameData,
+};
+use super::utils::raw::Flags;
+
+struct Loaded {
+    root: PathBuf,
+    map: i32,
+    /// `None` if the map has no script in the data folder.
+    script: Option<String>,
+}
+
+#[derive(Default)]
+pub struct ScriptViewer {
+    pub open: bool,
+    loaded: Option<Loaded>,
+}
+
+const COMMAND_COLOR: Color32 = Color32::from_rgb(110, 160, 230);
+const EVENT_COLOR: Color32 = Color32::from_rgb(230, 170, 80);
+
+/// Draw one line of a script; returns the flag clicked, if any.
+fn draw_line(ui: &mut Ui, line: &str, flags: &Flags, index: &FlagIndex) -> Option<usize> {
+    if line.starts_with('#') {
+        ui.label(RichText::new(line).monospace().strong().color(EVENT_COLOR));
+        return None;
+    }
+
+    let mut clicked = None;
+    ui.horizontal_wrapped(|ui| {
+        ui.spacing_mut().item_spacing.x = 0.;
+        for token in tsc::tokens(line) {
+            match token {
+                Token::Text(text) => {
+                    ui.label(RichText::new(text).monospace());
+                }
+                Token::Command { name, args, source } => {
+                    if !FLAG_COMMANDS.contains(&name) || args.is_empty() {
+                        ui.label(RichText::new(source).monospace().color(COMMAND_COLOR));
+                        continue;
+                    }
+                    let flag = usize::from(args[0]);
+                    let state = if flags.get(flag) { "set" } else { "not set" };
+                    // the name is ASCII, arguments may not be
+                    let (head, args) = source.split_at(4);
+                    let (arg, rest) = match (args.get(..4), args.get(4..)) {
+                        (Some(arg), Some(rest)) => (arg, rest),
+                        _ => (args, ""),
+                    };
+                    ui.label(RichText::new(head).monospace().color(COMMAND_COLOR));
+                    if ui
+                        .link(RichText::new(arg).monospace())
+                        .on_hover_ui(|ui| {
+                            ui.label(format!("flag {flag} is {state} in this profile"));
+                            ui.label(index.describe(flag));
+                        })
+                        .clicked()
+                    {
+                        clicked = Some(flag);
+                    }
+                    ui.label(RichText::new(rest).monospace().color(COMMAND_COLOR));
+                }
+            }
+        }
+    });
+    clicked
+}
+
+impl ScriptViewer {
+    /// Show the script of `map`; returns the flag clicked, if any.
+    pub fn show(
+        &mut self,
+        ctx: &Context,
+        data: Option<&GameData>,
+        map: i32,
+        flags: &Flags,
+    ) -> Option<usize> {
+        let mut open = self.open;
+        let mut clicked = None;
+        egui::Window::new("Map Script")
+            .open(&mut open)
+            .default_size([480., 400.])
+            .show(ctx, |ui| {
+                let Some(data) = data else {
+                    ui.label("Choose a game data folder in the Data menu to read map scripts.");
+                    return;
+                };
+
+                let outdated = self
+                    .loaded
+                    .as_ref()
+                    .is_none_or(|l| l.map != map || l.root != data.root);
+                if outdated {
+                    self.loaded = Some(Loaded {
+                        root: data.root.clone(),
+                        map,
+                        script: data.stage_script(map),
+                    });
+                }
+                let Some(script) = self.loaded.as_ref().and_then(|l| l.script.as_ref()) else {
+                    ui.label("This map's script was not found in the data folder.");
+                    return;
+                };
+
+                ui.label("click a flag number to find it in the profile's flags");
+                ui.separator();
+                ScrollArea::both().auto_shrink(false).show(ui, |ui| {
+                    for line in script.lines() {
+                        if let Some(flag) = draw_line(ui, line, flags, &data.flags) {
+                            clicked = Some(flag);
+                        }
+                    }
+                });
+            });
+        self.open = open;
+        clicked
+    }
+}
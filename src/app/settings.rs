@@ -0,0 +1,411 @@
+//! `settings.json` of doukutsu-rs, stored next to its profiles.
+//!
+//! Keys known to the editor get a dedicated control and are checked against
+//! their expected type; every other key is kept as is.
+
+use egui::{Context, DragValue, Ui};
+use serde_json::{Map, Value};
+
+use super::storage::{Storage, StorageIO, SETTINGS};
+use super::utils::load_error;
+
+enum Kind {
+    Bool,
+    Volume,
+    /// Window scaling factor, at least 1
+    Scale,
+    Choice(&'static [&'static str]),
+    Text,
+    /// Object of key name to key code, as in `player1_key_map`
+    KeyMap,
+}
+
+struct Key {
+    name: &'static str,
+    label: &'static str,
+    kind: Kind,
+    default: fn() -> Value,
+}
+
+const fn key(name: &'static str, label: &'static str, kind: Kind, default: fn() -> Value) -> Key {
+    Key {
+        name,
+        label,
+        kind,
+        default,
+    }
+}
+
+const KEY_MAP_ACTIONS: [&str; 12] = [
+    "left",
+    "up",
+    "right",
+    "down",
+    "prev_weapon",
+    "next_weapon",
+    "jump",
+    "shoot",
+    "skip",
+    "inventory",
+    "map",
+    "strafe",
+];
+
+static GROUPS: &[(&str, &[Key])] = &[
+    (
+        "Renderer",
+        &[
+            key(
+                "window_mode",
+                "window mode",
+                Kind::Choice(&["Windowed", "Fullscreen"]),
+                || "Windowed".into(),
+            ),
+            key(
+                "vsync_mode",
+                "vsync",
+                Kind::Choice(&[
+                    "Uncapped",
+                    "VSync",
+                    "VRRTickSync1x",
+                    "VRRTickSync2x",
+                    "VRRTickSync3x",
+                ]),
+                || "VSync".into(),
+            ),
+            key(
+                "timing_mode",
+                "timing",
+                Kind::Choice(&["_50Hz", "_60Hz", "FrameSynchronized"]),
+                || "_50Hz".into(),
+            ),
+            key("scale", "scaling", Kind::Scale, || 2.0.into()),
+            key(
+                "motion_interpolation",
+                "motion interpolation",
+                Kind::Bool,
+                || true.into(),
+            ),
+            key("subpixel_coords", "subpixel scrolling", Kind::Bool, || {
+                true.into()
+            }),
+            key("original_textures", "original textures", Kind::Bool, || {
+                false.into()
+            }),
+            key("seasonal_textures", "seasonal textures", Kind::Bool, || {
+                true.into()
+            }),
+            key("lighting_efects", "lighting effects", Kind::Bool, || {
+                true.into()
+            }),
+        ],
+    ),
+    (
+        "Sound",
+        &[
+            key("soundtrack", "soundtrack", Kind::Text, || "organya".into()),
+            key(
+                "organya_interpolation",
+                "organya interpolation",
+                Kind::Choice(&["Nearest", "Linear", "Cosine", "Cubic", "Polyphase"]),
+                || "Linear".into(),
+            ),
+            key("bgm_volume", "music volume", Kind::Volume, || 1.0.into()),
+            key("sfx_volume", "effects volume", Kind::Volume, || 1.0.into()),
+        ],
+    ),
+    (
+        "Controls",
+        &[
+            key("player1_key_map", "player 1 keys", Kind::KeyMap, || {
+                Value::Object(Map::new())
+            }),
+            key("player2_key_map", "player 2 keys", Kind::KeyMap, || {
+                Value::Object(Map::new())
+            }),
+            key("touch_controls", "touch controls", Kind::Bool, || {
+                false.into()
+            }),
+            key(
+                "pause_on_focus_loss",
+                "pause on focus loss",
+                Kind::Bool,
+                || true.into(),
+            ),
+        ],
+    ),
+    (
+        "Language",
+        &[key("locale", "locale", Kind::Text, || "en".into())],
+    ),
+    (
+        "Debug",
+        &[
+            key("debug_mode", "debug mode", Kind::Bool, || false.into()),
+            key("noclip", "noclip", Kind::Bool, || false.into()),
+        ],
+    ),
+];
+
+impl Kind {
+    /// Whether `value` is what doukutsu-rs expects for this key.
+    fn validate(&self, value: &Value) -> Result<(), String> {
+        match (self, value) {
+            (Kind::Bool, Value::Bool(_)) | (Kind::Text, Value::String(_)) => Ok(()),
+            (Kind::Volume, Value::Number(n)) => match n.as_f64() {
+                Some(v) if (0.0..=1.0).contains(&v) => Ok(()),
+                _ => Err("expected a volume between 0 and 1".into()),
+            },
+            (Kind::Scale, Value::Number(n)) => match n.as_f64() {
+                Some(v) if v >= 1.0 => Ok(()),
+                _ => Err("expected a scale of at least 1".into()),
+            },
+            (Kind::Choice(choices), Value::String(s)) if choices.contains(&s.as_str()) => Ok(()),
+            (Kind::Choice(choices), _) => Err(format!("expected one of {}", choices.join(", "))),
+            (Kind::KeyMap, Value::Object(map)) => match map.iter().find(|(_, v)| !v.is_string()) {
+                Some((action, _)) => Err(format!("key of {action} should be a string")),
+                None => Ok(()),
+            },
+            (Kind::Bool, _) => Err("expected true or false".into()),
+            (Kind::Volume | Kind::Scale, _) => Err("expected a number".into()),
+            (Kind::Text, _) => Err("expected a string".into()),
+            (Kind::KeyMap, _) => Err("expected an object".into()),
+        }
+    }
+
+    fn edit(&self, ui: &mut Ui, id_salt: &str, value: &mut Value) {
+        match (self, value) {
+            (Kind::Bool, Value::Bool(b)) => {
+                ui.checkbox(b, "");
+            }
+            (Kind::Volume, value @ Value::Number(_)) => {
+                let mut volume = value.as_f64().unwrap_or_default();
+                if ui
+                    .add(
+                        DragValue::new(&mut volume)
+                            .range(0.0..=1.0)
+                            .speed(0.01)
+                            .clamp_existing_to_range(false),
+                    )
+                    .changed()
+                {
+                    *value = volume.into();
+                }
+            }
+            (Kind::Scale, value @ Value::Number(_)) => {
+                let mut scale = value.as_f64().unwrap_or(1.0);
+                if ui
+                    .add(
+                        DragValue::new(&mut scale)
+                            .range(1.0..=8.0)
+                            .speed(0.1)
+                            .clamp_existing_to_range(false),
+                    )
+                    .changed()
+                {
+                    *value = scale.into();
+                }
+            }
+            (Kind::Choice(choices), Value::String(s)) => {
+                egui::ComboBox::new(id_salt, "")
+                    .selected_text(s.as_str())
+                    .show_ui(ui, |ui| {
+                        for choice in *choices {
+                            ui.selectable_value(s, choice.to_string(), *choice);
+                        }
+                    });
+            }
+            (Kind::Text, Value::String(s)) => {
+                ui.text_edit_singleline(s);
+            }
+            (Kind::KeyMap, Value::Object(map)) => {
+                ui.vertical(|ui| {
+                    egui::Grid::new(id_salt).show(ui, |ui| {
+                        for action in KEY_MAP_ACTIONS {
+                            ui.label(action);
+                            match map.get_mut(action) {
+                                Some(Value::String(code)) => {
+                                    ui.text_edit_singleline(code);
+                                }
+                                Some(other) => {
+                                    ui.label(other.to_string());
+                                }
+                                None => {
+                                    if ui.button("set").clicked() {
+                                        map.insert(action.into(), String::new().into());
+                                    }
+                                }
+                            }
+                            ui.end_row();
+                        }
+                    });
+                });
+            }
+            // invalid values are reported, not edited
+            (_, value) => {
+                ui.label(value.to_string());
+            }
+        }
+    }
+}
+
+fn known(name: &str) -> bool {
+    GROUPS
+        .iter()
+        .flat_map(|(_, keys)| keys.iter())
+        .any(|k| k.name == name)
+}
+
+pub struct SettingsEditor {
+    storage: Storage,
+    settings: Option<Map<String, Value>>,
+}
+
+impl Default for SettingsEditor {
+    fn default() -> Self {
+        Self {
+            storage: Storage::new(&SETTINGS),
+            settings: None,
+        }
+    }
+}
+
+impl SettingsEditor {
+    pub fn update(&mut self, ctx: &Context) {
+        self.storage.drag_handle(ctx);
+        if let Some(data) = self.storage.try_read_data() {
+            match serde_json::from_slice(&data) {
+                Ok(Value::Object(settings)) => self.settings = Some(settings),
+                Ok(_) => load_error("settings should be a JSON object".into()),
+                Err(e) => load_error(e.to_string()),
+            }
+        }
+    }
+
+    pub fn file_ops(&mut self, ui: &mut Ui) {
+        if ui.button("Open settings.json").clicked() {
+            self.storage.open_dialog();
+        }
+    }
+
+    pub fn show(&mut self, ctx: &Context) {
+        let Some(settings) = &mut self.settings else {
+            return;
+        };
+        let mut open = true;
+        egui::Window::new("doukutsu-rs Settings")
+            .open(&mut open)
+            .vscroll(true)
+            .show(ctx, |ui| {
+                draw_window(ui, settings);
+                ui.separator();
+                if ui.button("Save").clicked() {
+                    if let Ok(data) = serde_json::to_vec_pretty(settings) {
+                        self.storage.try_write_data(&data);
+                    }
+                }
+            });
+        if !open {
+            self.settings = None;
+        }
+    }
+}
+
+fn draw_window(ui: &mut Ui, settings: &mut Map<String, Value>) {
+    for (group, keys) in GROUPS {
+        egui::CollapsingHeader::new(*group)
+            .default_open(true)
+            .show(ui, |ui| {
+                egui::Grid::new(format!("settings-{group}")).show(ui, |ui| {
+                    for key in keys.iter() {
+                        ui.label(key.label);
+                        match settings.get_mut(key.name) {
+                            Some(value) => {
+                                let invalid = key.kind.validate(value).err();
+                                key.kind.edit(ui, key.name, value);
+                                if let Some(reason) = invalid {
+                                    ui.colored_label(ui.visuals().warn_fg_color, "⚠")
+                                        .on_hover_text(reason);
+                                    if ui.button("reset").clicked() {
+                                        *value = (key.default)();
+                                    }
+                                }
+                            }
+                            None => {
+                                if ui.button("set").clicked() {
+                                    settings.insert(key.name.into(), (key.default)());
+                                }
+                            }
+                        }
+                        ui.end_row();
+                    }
+                });
+            });
+    }
+
+    egui::CollapsingHeader::new("Other keys").show(ui, |ui| {
+        ui.label("Kept unchanged when saving.");
+        for (name, value) in settings.iter().filter(|(name, _)| !known(name)) {
+            ui.label(format!("{name}: {value}"));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn keys() -> impl Iterator<Item = &'static Key> {
+        GROUPS.iter().flat_map(|(_, keys)| keys.iter())
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        for key in keys() {
+            assert!(key.kind.validate(&(key.default)()).is_ok(), "{}", key.name);
+        }
+    }
+
+    #[test]
+    fn unique_names() {
+        let mut names: Vec<_> = keys().map(|k| k.name).collect();
+        names.sort_unstable();
+        let count = names.len();
+        names.dedup();
+        assert_eq!(names.len(), count);
+    }
+
+    #[test]
+    fn known_keys() {
+        for name in [
+            "window_mode",
+            "scale",
+            "soundtrack",
+            "player1_key_map",
+            "locale",
+        ] {
+            assert!(known(name), "{name}");
+        }
+        assert!(!known("some_future_key"));
+    }
+
+    #[test]
+    fn validate() {
+        assert!(Kind::Bool.validate(&json!(1)).is_err());
+        assert!(Kind::Volume.validate(&json!(0.5)).is_ok());
+        assert!(Kind::Volume.validate(&json!(1.5)).is_err());
+        assert!(Kind::Scale.validate(&json!(3)).is_ok());
+        assert!(Kind::Scale.validate(&json!(0.5)).is_err());
+        assert!(Kind::Scale.validate(&json!("2")).is_err());
+
+        let choice = Kind::Choice(&["Windowed", "Fullscreen"]);
+        assert!(choice.validate(&json!("Fullscreen")).is_ok());
+        assert!(choice.validate(&json!("Borderless")).is_err());
+
+        assert!(Kind::KeyMap.validate(&json!({"left": "Left"})).is_ok());
+        assert!(Kind::KeyMap.validate(&json!({"left": 37})).is_err());
+        assert!(Kind::KeyMap.validate(&json!([])).is_err());
+    }
+}
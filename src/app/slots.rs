@@ -0,0 +1,193 @@
+//! Reordering weapon and inventory slots, keeping used slots before empty ones.
+
+use egui::{Id, Response, Stroke, StrokeKind, Ui};
+
+pub enum SlotAction {
+    /// Move a slot, shifting the ones in between.
+    Move(usize, usize),
+    /// Remove a slot, compacting the ones after it.
+    Remove(usize),
+    /// Insert a slot holding an id before the given one. Never an empty slot,
+    /// which would hide the slots after it from the game.
+    Insert(usize, i32),
+}
+
+impl SlotAction {
+    /// Apply to the `num` used slots; `new` makes an inserted slot from its id.
+    pub fn apply<T: Default>(
+        self,
+        slots: &mut [T],
+        unknown: &mut [Option<i32>],
+        num: &mut usize,
+        new: impl FnOnce(i32) -> (T, Option<i32>),
+    ) {
+        match self {
+            SlotAction::Move(from, to) if from < to && to < *num => {
+                slots[from..=to].rotate_left(1);
+                unknown[from..=to].rotate_left(1);
+            }
+            SlotAction::Move(from, to) if to < from && from < *num => {
+                slots[to..=from].rotate_right(1);
+                unknown[to..=from].rotate_right(1);
+            }
+            SlotAction::Move(..) => {}
+            SlotAction::Remove(at) if at < *num => {
+                slots[at..*num].rotate_left(1);
+                unknown[at..*num].rotate_left(1);
+                *num -= 1;
+                slots[*num] = T::default();
+                unknown[*num] = None;
+            }
+            SlotAction::Remove(_) => {}
+            SlotAction::Insert(at, id) if id != 0 && at <= *num && *num < slots.len() => {
+                slots[at..=*num].rotate_right(1);
+                unknown[at..=*num].rotate_right(1);
+                (slots[at], unknown[at]) = new(id);
+                *num += 1;
+            }
+            SlotAction::Insert(..) => {}
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Dragged {
+    kind: &'static str,
+    pos: usize,
+}
+
+/// Drag handle of a slot, with a menu to insert or remove slots; `pick` shows
+/// the picker of the id to insert.
+pub fn handle(
+    ui: &mut Ui,
+    kind: &'static str,
+    pos: usize,
+    can_insert: bool,
+    action: &mut Option<SlotAction>,
+    pick: impl FnOnce(&mut Ui) -> Option<i32>,
+) {
+    let response = ui
+        .dnd_drag_source(Id::new((kind, pos)), Dragged { kind, pos }, |ui| {
+            ui.label("☰");
+        })
+        .response
+        .on_hover_text("drag to reorder, right-click for more");
+    response.context_menu(|ui| {
+        ui.add_enabled_ui(can_insert, |ui| {
+            ui.menu_button("Insert before", |ui| {
+                if let Some(id) = pick(ui) {
+                    *action = Some(SlotAction::Insert(pos, id));
+                }
+            });
+        });
+        if ui.button("Remove").clicked() {
+            *action = Some(SlotAction::Remove(pos));
+        }
+    });
+}
+
+/// Accept slots of the same `kind` dropped on `response`, the area of slot `pos`.
+pub fn drop_target(
+    ui: &Ui,
+    response: &Response,
+    kind: &'static str,
+    pos: usize,
+    action: &mut Option<SlotAction>,
+) {
+    let same_kind = |dragged: &Dragged| dragged.kind == kind;
+    if response
+        .dnd_hover_payload::<Dragged>()
+        .is_some_and(|d| same_kind(&d))
+    {
+        let stroke = Stroke::new(2., ui.visuals().selection.stroke.color);
+        ui.painter()
+            .rect_stroke(response.rect, 2., stroke, StrokeKind::Inside);
+    }
+    if let Some(dragged) = response
+        .dnd_release_payload::<Dragged>()
+        .filter(|d| same_kind(d))
+    {
+        *action = Some(SlotAction::Move(dragged.pos, pos));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cavestory_save::items::Inventory;
+
+    use super::*;
+    use crate::app::utils::raw::GameId;
+
+    /// Apply `action` to slots `1..=num` out of four.
+    fn apply(action: SlotAction, num: usize) -> (Vec<i32>, usize) {
+        let mut slots: Vec<i32> = (1..=4)
+            .map(|i| if i <= num { i as i32 } else { 0 })
+            .collect();
+        let mut unknown = vec![None; slots.len()];
+        let mut num = num;
+        action.apply(&mut slots, &mut unknown, &mut num, |id| (id, None));
+        (slots, num)
+    }
+
+    #[test]
+    fn move_slot() {
+        assert_eq!(apply(SlotAction::Move(0, 2), 3), (vec![2, 3, 1, 0], 3));
+        assert_eq!(apply(SlotAction::Move(2, 0), 3), (vec![3, 1, 2, 0], 3));
+        assert_eq!(apply(SlotAction::Move(1, 1), 3), (vec![1, 2, 3, 0], 3));
+        // empty slots can't be moved to or from
+        assert_eq!(apply(SlotAction::Move(0, 3), 3), (vec![1, 2, 3, 0], 3));
+        assert_eq!(apply(SlotAction::Move(3, 0), 3), (vec![1, 2, 3, 0], 3));
+    }
+
+    #[test]
+    fn remove() {
+        assert_eq!(apply(SlotAction::Remove(0), 3), (vec![2, 3, 0, 0], 2));
+        assert_eq!(apply(SlotAction::Remove(2), 3), (vec![1, 2, 0, 0], 2));
+        assert_eq!(apply(SlotAction::Remove(0), 1), (vec![0, 0, 0, 0], 0));
+        assert_eq!(apply(SlotAction::Remove(3), 3), (vec![1, 2, 3, 0], 3));
+    }
+
+    #[test]
+    fn insert() {
+        assert_eq!(apply(SlotAction::Insert(0, 9), 3), (vec![9, 1, 2, 3], 4));
+        assert_eq!(apply(SlotAction::Insert(3, 9), 3), (vec![1, 2, 3, 9], 4));
+        assert_eq!(apply(SlotAction::Insert(0, 9), 0), (vec![9, 0, 0, 0], 1));
+        // full, or past the used slots
+        assert_eq!(apply(SlotAction::Insert(4, 9), 4), (vec![1, 2, 3, 4], 4));
+        assert_eq!(apply(SlotAction::Insert(0, 9), 4), (vec![1, 2, 3, 4], 4));
+        assert_eq!(apply(SlotAction::Insert(3, 9), 2), (vec![1, 2, 0, 0], 2));
+    }
+
+    #[test]
+    fn insert_empty_slot() {
+        // an empty slot between used ones would end the slots in game
+        assert_eq!(apply(SlotAction::Insert(1, 0), 3), (vec![1, 2, 3, 0], 3));
+        assert_eq!(apply(SlotAction::Insert(3, 0), 3), (vec![1, 2, 3, 0], 3));
+    }
+
+    #[test]
+    fn insert_unknown_id() {
+        let mut slots = [Inventory::MapSystem, Inventory::None];
+        let mut unknown = [None; 2];
+        let mut num = 1;
+        SlotAction::Insert(0, 77).apply(
+            &mut slots,
+            &mut unknown,
+            &mut num,
+            Inventory::with_unknown,
+        );
+        assert_eq!(slots, [Inventory::None, Inventory::MapSystem]);
+        assert_eq!((unknown, num), ([Some(77), None], 2));
+    }
+
+    #[test]
+    fn unknown_follows_its_slot() {
+        let mut slots = [1, 2, 3];
+        let mut unknown = [None, Some(42), None];
+        let mut num = 3;
+        SlotAction::Move(1, 2).apply(&mut slots, &mut unknown, &mut num, |id| (id, None));
+        assert_eq!(unknown, [None, None, Some(42)]);
+        SlotAction::Remove(2).apply(&mut slots, &mut unknown, &mut num, |id| (id, None));
+        assert_eq!((slots, unknown, num), ([1, 3, 0], [None; 3], 2));
+    }
+}
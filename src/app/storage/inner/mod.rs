@@ -5,15 +5,25 @@
 
 use kanal::{unbounded, Receiver, Sender};
 
+use super::{FileKind, PROFILE};
+
 pub struct Storage {
+    kind: &'static FileKind,
     data: Option<Vec<u8>>,
     data_sender: Sender<Vec<u8>>,
     data_recv: Receiver<Vec<u8>>,
 }
 impl Default for Storage {
     fn default() -> Self {
+        Self::new(&PROFILE)
+    }
+}
+
+impl Storage {
+    pub fn new(kind: &'static FileKind) -> Self {
         let (sender, recv) = unbounded();
         Self {
+            kind,
             data: Default::default(),
             data_sender: sender,
             data_recv: recv,
@@ -23,10 +33,18 @@ impl Default for Storage {
 
 impl super::StorageIO for Storage {
     fn drag_handle(&mut self, ctx: &egui::Context) {
+        let accepts = |file: &egui::DroppedFile| {
+            let name = match &file.path {
+                Some(path) => path.file_name().map(|n| n.to_string_lossy().into_owned()),
+                None => Some(file.name.clone()),
+            };
+            name.is_some_and(|name| (self.kind.accepts)(&name))
+        };
+
         #[cfg(target_arch = "wasm32")]
         let dragged_path: Option<_> = ctx.input(|i| {
             let dropped_files = &i.raw.dropped_files;
-            let file = dropped_files.get(0)?;
+            let file = dropped_files.iter().find(|f| accepts(f))?;
             let bytes = file.bytes.clone()?;
             Some(bytes.to_vec())
         });
@@ -35,7 +53,7 @@ impl super::StorageIO for Storage {
         #[cfg(not(target_arch = "wasm32"))]
         let dragged_path: Option<_> = ctx.input(|i| {
             let dropped_files = &i.raw.dropped_files;
-            let file = dropped_files.get(0)?;
+            let file = dropped_files.iter().find(|f| accepts(f))?;
 
             file.path
                 .iter()
@@ -45,7 +63,7 @@ impl super::StorageIO for Storage {
 
         if let Some(data) = dragged_path {
             let _ = self.data_sender.send(data);
-            ctx.input_mut(|i| i.raw.dropped_files.clear());
+            ctx.input_mut(|i| i.raw.dropped_files.retain(|f| !accepts(f)));
         }
     }
 
@@ -59,11 +77,12 @@ impl super::StorageIO for Storage {
 
     fn try_write_data(&self, data: &[u8]) {
         let data: Vec<u8> = data.into();
+        let kind = self.kind;
         let future = async move {
             if let Some(handle) = rfd::AsyncFileDialog::default()
-                .add_filter("Profile", &["dat"])
-                .set_title("Save your game profile")
-                .set_file_name("profile.dat")
+                .add_filter(kind.filter, kind.extensions)
+                .set_title(format!("Save your {}", kind.description))
+                .set_file_name(kind.default_name)
                 .save_file()
                 .await
             {
@@ -79,10 +98,11 @@ impl super::StorageIO for Storage {
 
     fn open_dialog(&self) {
         let sender = self.data_sender.clone();
+        let kind = self.kind;
         let future = async move {
             if let Some(handle) = rfd::AsyncFileDialog::default()
-                .add_filter("Profile", &["dat"])
-                .set_title("Pick your game profile")
+                .add_filter(kind.filter, kind.extensions)
+                .set_title(format!("Pick your {}", kind.description))
                 .pick_file()
                 .await
             {
@@ -6,6 +6,64 @@
 mod inner;
 pub use inner::Storage;
 
+/// A kind of file handled by a [`Storage`].
+pub struct FileKind {
+    /// Name of the dialog filter
+    pub filter: &'static str,
+    pub extensions: &'static [&'static str],
+    /// Used in dialog titles, as in "Pick your ..."
+    pub description: &'static str,
+    pub default_name: &'static str,
+    /// Whether a dropped file of this name belongs here
+    pub accepts: fn(&str) -> bool,
+}
+
+pub const PROFILE: FileKind = FileKind {
+    filter: "Profile",
+    extensions: &["dat"],
+    description: "game profile",
+    default_name: "profile.dat",
+    // any other file, as profiles are often renamed or kept as backups
+    accepts: |name| {
+        ![NIKUMARU, CONFIG, SETTINGS]
+            .iter()
+            .any(|kind| (kind.accepts)(name))
+    },
+};
+
+pub const NIKUMARU: FileKind = FileKind {
+    filter: "Nikumaru counter",
+    extensions: &["rec"],
+    description: "Nikumaru counter",
+    default_name: "290.rec",
+    accepts: |name| name.to_ascii_lowercase().ends_with(".rec"),
+};
+
+pub const CONFIG: FileKind = FileKind {
+    filter: "Config",
+    extensions: &["dat"],
+    description: "game config",
+    default_name: "Config.dat",
+    accepts: |name| name.eq_ignore_ascii_case("config.dat"),
+};
+
+pub const SETTINGS: FileKind = FileKind {
+    filter: "doukutsu-rs settings",
+    extensions: &["json"],
+    description: "doukutsu-rs settings",
+    default_name: "settings.json",
+    accepts: |name| name.to_ascii_lowercase().ends_with(".json"),
+};
+
+pub const WAV: FileKind = FileKind {
+    filter: "WAV audio",
+    extensions: &["wav"],
+    description: "song",
+    default_name: "song.wav",
+    // only ever written
+    accepts: |_| false,
+};
+
 pub trait StorageIO {
     fn drag_handle(&mut self, ctx: &egui::Context);
     fn try_read_data(&mut self) -> Option<Vec<u8>>;
@@ -0,0 +1,106 @@
+use cavestory_save::items::{Inventory, WeaponType};
+
+use super::data::{vanilla, xref::Trade, NameTable};
+use super::inventory;
+use super::widgets::{name_of, set_id};
+use crate::MainApp;
+
+/// Trades of the vanilla game, used without a data folder.
+pub fn builtin_trades() -> Vec<Trade> {
+    vanilla::TRADES
+        .iter()
+        .map(|trade| Trade {
+            from: trade.from,
+            to: trade.to,
+            ammo: trade.ammo,
+            source: None,
+            flags_set: trade.flags_set.to_vec(),
+            flags_cleared: trade.flags_cleared.to_vec(),
+            items_added: trade.items_added.to_vec(),
+            items_removed: trade.items_removed.to_vec(),
+        })
+        .collect()
+}
+
+/// What a trade does besides changing the weapon, for tooltips.
+pub fn describe(trade: &Trade, names: Option<&NameTable>) -> String {
+    let flags = |flags: &[u16]| {
+        flags
+            .iter()
+            .map(|f| format!("{f:04}"))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let items = |items: &[i32]| {
+        items
+            .iter()
+            .map(|&i| name_of::<Inventory>(i, names))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let mut lines = vec![match &trade.source {
+        Some((script, event)) => format!("as in {script} #{event:04}"),
+        None if trade.flags_set.is_empty() && trade.flags_cleared.is_empty() => {
+            "vanilla trade, its flags and items need a data folder".to_owned()
+        }
+        None => "vanilla trade".to_owned(),
+    }];
+    lines.push("resets the weapon to level 1".to_owned());
+    if trade.ammo != 0 {
+        lines.push(format!("adds {} ammo", trade.ammo));
+    }
+    for (label, list) in [
+        ("sets flags", &trade.flags_set),
+        ("clears flags", &trade.flags_cleared),
+    ] {
+        if !list.is_empty() {
+            lines.push(format!("{label} {}", flags(list)));
+        }
+    }
+    for (label, list) in [
+        ("gives", &trade.items_added),
+        ("takes", &trade.items_removed),
+    ] {
+        if !list.is_empty() {
+            lines.push(format!("{label} {}", items(list)));
+        }
+    }
+    lines.join("\n")
+}
+
+impl MainApp {
+    /// Trade the weapon in `slot` as the game's `<TAM` does, along with the event's flags and items.
+    pub(super) fn apply_trade(&mut self, slot: usize, trade: &Trade) {
+        let Some((_, profile)) = &mut self.profile else {
+            return;
+        };
+
+        let weapon = &mut profile.weapon[slot];
+        set_id::<WeaponType>(
+            &mut weapon.classification,
+            &mut self.unknown.weapon[slot],
+            trade.to,
+        );
+        weapon.level = 1;
+        weapon.exp = 0;
+        weapon.max_ammo += trade.ammo;
+        weapon.ammo += trade.ammo;
+
+        for &flag in &trade.flags_set {
+            self.flags.set(flag.into(), true);
+        }
+        for &flag in &trade.flags_cleared {
+            self.flags.set(flag.into(), false);
+        }
+
+        let inventory = &mut profile.inventory;
+        let unknown = &mut self.unknown.inventory;
+        for &item in &trade.items_removed {
+            inventory::take(inventory, unknown, &mut self.inventory_num, item);
+        }
+        for &item in &trade.items_added {
+            inventory::give(inventory, unknown, &mut self.inventory_num, item);
+        }
+    }
+}
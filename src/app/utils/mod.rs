@@ -1,17 +1,34 @@
 use cavestory_save::{
-    items::{
-        EquipOpt, Equipment, Inventory, Teleporter, TeleporterLocation, TeleporterMenu, WeaponType,
-    },
-    strum::IntoEnumIterator,
+    items::{Inventory, Teleporter, TeleporterLocation, TeleporterMenu, WeaponType},
     GameProfile, Profile, ProfileError,
 };
 
 use crate::MainApp;
 
+use self::raw::{EquipBits, Flags, Stars, UnknownIds};
+
+pub mod raw;
+
+/// Report a file which couldn't be loaded.
+pub fn load_error(description: String) {
+    use rfd::{AsyncMessageDialog, MessageLevel};
+    let future = async move {
+        AsyncMessageDialog::new()
+            .set_level(MessageLevel::Error)
+            .set_title("Load Error")
+            .set_description(&description)
+            .show()
+            .await;
+    };
+    #[cfg(target_arch = "wasm32")]
+    let _ = poll_promise::Promise::spawn_local(future);
+    #[cfg(not(target_arch = "wasm32"))]
+    let _ = crate::TASK_SENDER.get().unwrap().send(Box::pin(future));
+}
+
 pub trait ProfileExt {
     fn verify_and_init(&mut self, data: Vec<u8>) -> Result<(), ProfileError>;
     fn update_state(&mut self) -> Option<()>;
-    fn detect_equip(&self) -> Option<[bool; 9]>;
     fn count_weapon(&self) -> Option<usize>;
     fn count_inventory(&self) -> Option<usize>;
     fn enable_all_teleporters(gp: &mut GameProfile);
@@ -19,28 +36,28 @@ pub trait ProfileExt {
 
 impl ProfileExt for MainApp {
     fn verify_and_init(&mut self, data: Vec<u8>) -> Result<(), ProfileError> {
-        match Profile::from_raw_without_length_check(data) {
-            Ok(profile) => {
-                let game_profile = GameProfile::dump(&profile);
+        let unknown = UnknownIds::scan(&data);
+        let flags = Flags::scan(&data);
+        let equip_bits = EquipBits::scan(&data);
+        let stars = Stars::scan(&data);
+        let mut sanitized = data.clone();
+        unknown.sanitize(&mut sanitized);
+
+        match Profile::from_raw_without_length_check(data)
+            .and_then(|p| Ok((p, Profile::from_raw_without_length_check(sanitized)?)))
+        {
+            Ok((profile, sanitized)) => {
+                let game_profile = GameProfile::dump(&sanitized);
                 self.profile = Some((profile, game_profile));
+                self.unknown = unknown;
+                self.flags = flags;
+                self.equip_bits = equip_bits;
+                self.stars = stars;
                 self.update_state();
                 Ok(())
             }
             Err(e) => {
-                use rfd::{AsyncMessageDialog, MessageLevel};
-                let future = async move {
-                    AsyncMessageDialog::new()
-                        .set_level(MessageLevel::Error)
-                        .set_title("Load Error")
-                        .set_description(&e.to_string())
-                        .show()
-                        .await;
-                };
-                #[cfg(target_arch = "wasm32")]
-                let _ = poll_promise::Promise::spawn_local(future);
-                #[cfg(not(target_arch = "wasm32"))]
-                let _ = crate::TASK_SENDER.get().unwrap().send(Box::pin(future));
-
+                load_error(e.to_string());
                 Err(e)
             }
         }
@@ -49,32 +66,19 @@ impl ProfileExt for MainApp {
     fn update_state(&mut self) -> Option<()> {
         self.weapon_num = self.count_weapon()?;
         self.inventory_num = self.count_inventory()?;
-        self.equip_checked = self.detect_equip()?;
         Some(())
     }
 
-    fn detect_equip(&self) -> Option<[bool; 9]> {
-        self.profile
-            .as_ref()
-            .map(|(_, GameProfile { equipment, .. })| {
-                let mut equip_checked: [bool; 9] = Default::default();
-
-                let equip_current = equipment;
-                for (i, equip) in Equipment::iter().enumerate() {
-                    equip_checked[i] = equip_current.check(equip);
-                }
-
-                equip_checked
-            })
-    }
-
     fn count_weapon(&self) -> Option<usize> {
         self.profile
             .as_ref()
             .map(|(_, GameProfile { weapon, .. })| {
                 weapon
                     .iter()
-                    .take_while(|w| w.classification != WeaponType::None)
+                    .zip(&self.unknown.weapon)
+                    .take_while(|(w, unknown)| {
+                        w.classification != WeaponType::None || unknown.is_some()
+                    })
                     .count()
             })
     }
@@ -85,7 +89,8 @@ impl ProfileExt for MainApp {
             .map(|(_, GameProfile { inventory, .. })| {
                 inventory
                     .iter()
-                    .take_while(|&&i| i != Inventory::None)
+                    .zip(&self.unknown.inventory)
+                    .take_while(|(i, unknown)| **i != Inventory::None || unknown.is_some())
                     .count()
             })
     }
@@ -0,0 +1,281 @@
+//! Direct access to the `Profile.dat` layout, for the values
+//! `GameProfile` cannot represent.
+
+use cavestory_save::{
+    items::{Inventory, Map, Song, WeaponType},
+    strum::IntoEnumIterator,
+};
+
+pub const MAP_OFFSET: usize = 0x08;
+pub const MUSIC_OFFSET: usize = 0x0C;
+pub const WEAPON_OFFSET: usize = 0x38;
+pub const WEAPON_STRIDE: usize = 0x14;
+pub const WEAPON_SLOTS: usize = 8;
+pub const INVENTORY_OFFSET: usize = 0xD8;
+pub const INVENTORY_SLOTS: usize = 32;
+
+pub fn read_i32(data: &[u8], offset: usize) -> Option<i32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(i32::from_le_bytes(bytes.try_into().ok()?))
+}
+
+pub fn write_i32(data: &mut [u8], offset: usize, value: i32) {
+    if let Some(bytes) = data.get_mut(offset..offset + 4) {
+        bytes.copy_from_slice(&value.to_le_bytes());
+    }
+}
+
+/// Numeric id of an item enum, as stored in the profile.
+pub trait GameId: Copy + PartialEq + IntoEnumIterator + ToString {
+    fn id(self) -> i32;
+
+    fn from_id(id: i32) -> Option<Self> {
+        Self::iter().find(|v| v.id() == id)
+    }
+
+    /// The variant of `id`, or the default one with `id` kept as unknown.
+    fn with_unknown(id: i32) -> (Self, Option<i32>)
+    where
+        Self: Default,
+    {
+        match Self::from_id(id) {
+            Some(value) => (value, None),
+            None => (Self::default(), Some(id)),
+        }
+    }
+}
+
+macro_rules! impl_game_id {
+    ($($ty:ty),*) => {
+        $(impl GameId for $ty {
+            fn id(self) -> i32 {
+                self as i32
+            }
+        })*
+    };
+}
+
+impl_game_id!(Map, Song, WeaponType, Inventory);
+
+/// Ids found in a profile which have no matching enum variant,
+/// usually maps, songs, weapons or items added by mods.
+#[derive(Clone, Default)]
+pub struct UnknownIds {
+    pub map: Option<i32>,
+    pub music: Option<i32>,
+    pub weapon: [Option<i32>; WEAPON_SLOTS],
+    pub inventory: [Option<i32>; INVENTORY_SLOTS],
+}
+
+impl UnknownIds {
+    pub fn scan(data: &[u8]) -> Self {
+        fn unknown<T: GameId>(data: &[u8], offset: usize) -> Option<i32> {
+            read_i32(data, offset).filter(|&id| T::from_id(id).is_none())
+        }
+
+        Self {
+            map: unknown::<Map>(data, MAP_OFFSET),
+            music: unknown::<Song>(data, MUSIC_OFFSET),
+            weapon: std::array::from_fn(|i| {
+                unknown::<WeaponType>(data, WEAPON_OFFSET + i * WEAPON_STRIDE)
+            }),
+            inventory: std::array::from_fn(|i| {
+                unknown::<Inventory>(data, INVENTORY_OFFSET + i * 4)
+            }),
+        }
+    }
+
+    fn offsets(&self) -> impl Iterator<Item = (usize, Option<i32>)> + '_ {
+        [(MAP_OFFSET, self.map), (MUSIC_OFFSET, self.music)]
+            .into_iter()
+            .chain(
+                self.weapon
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &id)| (WEAPON_OFFSET + i * WEAPON_STRIDE, id)),
+            )
+            .chain(
+                self.inventory
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &id)| (INVENTORY_OFFSET + i * 4, id)),
+            )
+    }
+
+    /// Replace unknown ids with `0`, so `GameProfile::dump` only sees known values.
+    pub fn sanitize(&self, data: &mut [u8]) {
+        for (offset, id) in self.offsets() {
+            if id.is_some() {
+                write_i32(data, offset, 0);
+            }
+        }
+    }
+
+    /// Write unknown ids back, after `GameProfile::write` overwrote them.
+    pub fn apply(&self, data: &mut [u8]) {
+        for (offset, id) in self.offsets() {
+            if let Some(id) = id {
+                write_i32(data, offset, id);
+            }
+        }
+    }
+}
+
+pub const FLAGS_OFFSET: usize = 0x21C;
+pub const FLAG_COUNT: usize = 8000;
+
+/// The profile's event flags, which `GameProfile` doesn't expose.
+#[derive(Clone, Default)]
+pub struct Flags(Vec<u8>);
+
+impl Flags {
+    pub fn scan(data: &[u8]) -> Self {
+        let mut bits = vec![0; FLAG_COUNT / 8];
+        if let Some(stored) = data.get(FLAGS_OFFSET..) {
+            let len = stored.len().min(bits.len());
+            bits[..len].copy_from_slice(&stored[..len]);
+        }
+        Self(bits)
+    }
+
+    pub fn get(&self, id: usize) -> bool {
+        self.0
+            .get(id / 8)
+            .is_some_and(|byte| byte & (1 << (id % 8)) != 0)
+    }
+
+    pub fn set(&mut self, id: usize, value: bool) {
+        if let Some(byte) = self.0.get_mut(id / 8) {
+            if value {
+                *byte |= 1 << (id % 8);
+            } else {
+                *byte &= !(1 << (id % 8));
+            }
+        }
+    }
+
+    pub fn apply(&self, data: &mut [u8]) {
+        if let Some(stored) = data.get_mut(FLAGS_OFFSET..) {
+            let len = stored.len().min(self.0.len());
+            stored[..len].copy_from_slice(&self.0[..len]);
+        }
+    }
+}
+
+pub const EQUIPMENT_OFFSET: usize = 0x2C;
+pub const EQUIPMENT_BITS: usize = 32;
+
+/// The whole equipment bitfield, including bits `Equipment` doesn't name.
+#[derive(Clone, Copy, Default)]
+pub struct EquipBits(pub u32);
+
+impl EquipBits {
+    pub fn scan(data: &[u8]) -> Self {
+        Self(read_i32(data, EQUIPMENT_OFFSET).unwrap_or(0) as u32)
+    }
+
+    pub fn get(self, bit: usize) -> bool {
+        self.0 & (1 << bit) != 0
+    }
+
+    pub fn set(&mut self, bit: usize, value: bool) {
+        if value {
+            self.0 |= 1 << bit;
+        } else {
+            self.0 &= !(1 << bit);
+        }
+    }
+
+    pub fn apply(self, data: &mut [u8]) {
+        write_i32(data, EQUIPMENT_OFFSET, self.0 as i32);
+    }
+}
+
+pub const STARS_OFFSET: usize = 0x1E;
+
+/// Stars orbiting the player with the Whimsical Star, stored as a 16-bit value.
+#[derive(Clone, Copy, Default)]
+pub struct Stars(pub i16);
+
+impl Stars {
+    pub fn scan(data: &[u8]) -> Self {
+        let bytes = data.get(STARS_OFFSET..STARS_OFFSET + 2);
+        Self(bytes.map_or(0, |b| i16::from_le_bytes([b[0], b[1]])))
+    }
+
+    pub fn apply(self, data: &mut [u8]) {
+        if let Some(bytes) = data.get_mut(STARS_OFFSET..STARS_OFFSET + 2) {
+            bytes.copy_from_slice(&self.0.to_le_bytes());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cavestory_save::{GameProfile, Profile};
+
+    use super::*;
+
+    const PROFILE_LEN: usize = 0x604;
+
+    /// A blank profile with a mod's map, song, weapon and item ids.
+    fn modded_profile() -> Vec<u8> {
+        let mut data = vec![0; PROFILE_LEN];
+        data[..8].copy_from_slice(b"Do041220");
+        write_i32(&mut data, MAP_OFFSET, 120);
+        write_i32(&mut data, MUSIC_OFFSET, 50);
+        write_i32(&mut data, WEAPON_OFFSET, WeaponType::PolarStar.id());
+        write_i32(&mut data, WEAPON_OFFSET + WEAPON_STRIDE, 42);
+        write_i32(&mut data, INVENTORY_OFFSET, 77);
+        write_i32(&mut data, INVENTORY_OFFSET + 4, Inventory::MapSystem.id());
+        data
+    }
+
+    #[test]
+    fn scan_finds_unknown_ids() {
+        let unknown = UnknownIds::scan(&modded_profile());
+        assert_eq!(unknown.map, Some(120));
+        assert_eq!(unknown.music, Some(50));
+        assert_eq!(unknown.weapon[..3], [None, Some(42), None]);
+        assert_eq!(unknown.inventory[..3], [Some(77), None, None]);
+    }
+
+    #[test]
+    fn unknown_ids_survive_a_save() {
+        let data = modded_profile();
+        let unknown = UnknownIds::scan(&data);
+        let mut sanitized = data.clone();
+        unknown.sanitize(&mut sanitized);
+        assert_eq!(read_i32(&sanitized, MAP_OFFSET), Some(0));
+        assert_eq!(read_i32(&sanitized, WEAPON_OFFSET + WEAPON_STRIDE), Some(0));
+
+        // edit a known value, then save the way the editor does
+        let mut game_profile =
+            GameProfile::dump(&Profile::from_raw_without_length_check(sanitized).unwrap());
+        game_profile.max_health = 10;
+        let mut profile = Profile::from_raw_without_length_check(data.clone()).unwrap();
+        game_profile.write(&mut profile);
+        let mut saved: Vec<u8> = profile.into();
+        unknown.apply(&mut saved);
+
+        assert_eq!(UnknownIds::scan(&saved).map, Some(120));
+        for offset in [
+            MAP_OFFSET,
+            MUSIC_OFFSET,
+            WEAPON_OFFSET,
+            WEAPON_OFFSET + WEAPON_STRIDE,
+            INVENTORY_OFFSET,
+            INVENTORY_OFFSET + 4,
+        ] {
+            assert_eq!(
+                read_i32(&saved, offset),
+                read_i32(&data, offset),
+                "{offset:#x}"
+            );
+        }
+        assert_eq!(
+            GameProfile::dump(&Profile::from_raw_unchecked(saved)).max_health,
+            10
+        );
+    }
+}
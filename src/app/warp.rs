@@ -0,0 +1,75 @@
+use cavestory_save::items::{Map, Position, Song};
+use egui::Ui;
+
+use super::data::GameData;
+use super::preview::tile_center;
+use super::utils::raw::{GameId, UnknownIds};
+use super::widgets::{name_of, set_id};
+
+/// A place worth warping to.
+#[derive(Clone)]
+pub struct Spot {
+    pub map: i32,
+    pub label: String,
+    /// Position, in tiles.
+    pub x: i32,
+    pub y: i32,
+    /// Music of the map, if it's known.
+    pub music: Option<i32>,
+}
+
+/// Where a new game starts, the only spot known without a data folder: save
+/// points, doors and teleporters are read from the `.pxe` files of one.
+fn new_game() -> Spot {
+    Spot {
+        map: Map::StartPoint.id(),
+        label: "New game".to_owned(),
+        x: 10,
+        y: 8,
+        music: None,
+    }
+}
+
+/// "Warp to…" picker, setting the map, position and music at once.
+pub fn draw_picker(
+    ui: &mut Ui,
+    data: Option<&GameData>,
+    map: &mut Map,
+    music: &mut Song,
+    position: &mut Position,
+    unknown: &mut UnknownIds,
+) {
+    let builtin = [new_game()];
+    let from_data = data
+        .map(|data| data.spots.as_slice())
+        .filter(|s| !s.is_empty());
+    let spots = from_data.unwrap_or(&builtin);
+    let names = data.map(|data| &data.names);
+    let current = unknown.map.unwrap_or(map.id());
+
+    egui::ComboBox::new("warp", "")
+        .selected_text("Warp to…")
+        .width(200.)
+        .show_ui(ui, |ui| {
+            // spots of the current map first
+            let (here, elsewhere): (Vec<_>, Vec<_>) = spots.iter().partition(|s| s.map == current);
+            for spot in here.into_iter().chain(elsewhere) {
+                // skip spots a position can't hold
+                let (Some(x), Some(y)) = (tile_center(spot.x), tile_center(spot.y)) else {
+                    continue;
+                };
+                let text = format!("{}: {}", name_of::<Map>(spot.map, names), spot.label);
+                if ui.selectable_label(false, text).clicked() {
+                    set_id(map, &mut unknown.map, spot.map);
+                    position.x = x;
+                    position.y = y;
+                    if let Some(id) = spot.music {
+                        set_id(music, &mut unknown.music, id);
+                    }
+                }
+            }
+            if from_data.is_none() {
+                ui.weak("save points, doors and teleporters need a data folder");
+            }
+        });
+}
@@ -1,20 +1,36 @@
-use std::ops::{AddAssign, SubAssign};
-
-use cavestory_save::{
-    items::{Weapon, WeaponType},
-    strum::IntoEnumIterator,
+use std::{
+    cmp::Ordering,
+    ops::{AddAssign, SubAssign},
 };
-use egui::{DragValue, Slider, Ui};
 
-const MAX_WEAPON_NUM: usize = 7;
+use cavestory_save::items::{Weapon, WeaponType};
+use egui::{DragValue, ProgressBar, Slider, SliderClamping, Ui};
+
+use super::data::{vanilla, xref::Trade, NameTable};
+use super::icons::Sheet;
+use super::slots;
+use super::trade;
+use super::utils::raw::GameId;
+use super::widgets::{icon_combo, id_combo, id_label, id_options, name_of, pick_id};
 
-pub fn draw_window(ui: &mut Ui, weapon_num: &mut usize, weapon: &mut [Weapon]) {
+pub const MAX_WEAPON_NUM: usize = 7;
+
+pub fn draw_window(
+    ui: &mut Ui,
+    weapon_num: &mut usize,
+    weapon: &mut [Weapon],
+    unknown: &mut [Option<i32>],
+    names: Option<&NameTable>,
+    icons: Option<&Sheet>,
+    trades: &[Trade],
+) -> Option<(usize, usize)> {
     ui.horizontal(|ui| {
         // do not set the 8th weapon, you may go into issue.
         let could_add = (*weapon_num == 0
             || weapon
                 .get(*weapon_num - 1)
-                .is_some_and(|w| w.classification != WeaponType::None))
+                .is_some_and(|w| w.classification != WeaponType::None)
+            || unknown.get(*weapon_num - 1).is_some_and(Option::is_some))
             && *weapon_num < MAX_WEAPON_NUM;
 
         if ui.button(" + ").clicked() && could_add {
@@ -25,62 +41,255 @@ pub fn draw_window(ui: &mut Ui, weapon_num: &mut usize, weapon: &mut [Weapon]) {
         if ui.button(" - ").clicked() && could_sub {
             weapon_num.sub_assign(1);
             weapon[*weapon_num] = Weapon::default();
+            unknown[*weapon_num] = None;
         }
     });
 
     ui.separator();
 
+    let mut action = None;
+    let mut traded = None;
     let chunk_size = 3;
-    for (chunk_i, chunk) in weapon[..*weapon_num].chunks_mut(chunk_size).enumerate() {
+    for (chunk_i, (chunk, unknown_chunk)) in weapon[..*weapon_num]
+        .chunks_mut(chunk_size)
+        .zip(unknown.chunks_mut(chunk_size))
+        .enumerate()
+    {
         ui.horizontal(|ui| {
-            for (i, weapon) in chunk.iter_mut().enumerate() {
-                ui.vertical(|ui| {
-                    let pos = chunk_i * chunk_size + i;
-                    egui::ComboBox::new(format!("weapontype-box-{pos}"), "")
-                        .width(160.)
-                        .selected_text(weapon.classification.to_string())
-                        .show_ui(ui, |ui| {
-                            let mut iter = WeaponType::iter();
-                            // skip None unless it's latest using slot
-                            if pos + 1 < *weapon_num {
-                                iter.next();
-                            }
-                            for model in iter {
-                                ui.selectable_value(
+            for (i, (weapon, unknown)) in chunk.iter_mut().zip(unknown_chunk).enumerate() {
+                let pos = chunk_i * chunk_size + i;
+                let response = ui
+                    .vertical(|ui| {
+                        ui.horizontal(|ui| {
+                            let can_insert = *weapon_num < MAX_WEAPON_NUM;
+                            slots::handle(ui, "weapon", pos, can_insert, &mut action, |ui| {
+                                pick_id::<WeaponType>(ui, ("insert-weapon", pos), names)
+                            });
+                            // skip None unless it's the latest used slot, or a gap loaded as is
+                            let skip_none = pos + 1 < *weapon_num
+                                && (weapon.classification != WeaponType::None || unknown.is_some());
+                            let options = id_options::<WeaponType>(ui.ctx(), names, skip_none);
+                            match icons {
+                                Some(sheet) => icon_combo(
+                                    ui,
+                                    format!("weapontype-box-{pos}"),
+                                    120.,
                                     &mut weapon.classification,
-                                    model,
-                                    model.to_string(),
-                                );
+                                    unknown,
+                                    &options,
+                                    sheet,
+                                ),
+                                None => id_combo(
+                                    ui,
+                                    format!("weapontype-box-{pos}"),
+                                    id_label(weapon.classification, *unknown, names),
+                                    160.,
+                                    &mut weapon.classification,
+                                    unknown,
+                                    &options,
+                                ),
                             }
+                            let id = unknown.unwrap_or(weapon.classification.id());
+                            draw_trades(ui, pos, id, trades, names, &mut traded);
                         });
-                    if weapon.classification != WeaponType::None {
-                        ui.horizontal(|ui| {
-                            ui.vertical(|ui| {
-                                ui.label("level");
-                                ui.add(
-                                    Slider::new(&mut weapon.level, 0..=3)
-                                        .orientation(egui::SliderOrientation::Vertical),
-                                );
+                        if weapon.classification != WeaponType::None || unknown.is_some() {
+                            let id = unknown.unwrap_or(weapon.classification.id());
+                            ui.horizontal(|ui| {
+                                ui.vertical(|ui| draw_level(ui, weapon, vanilla::arms_level(id)));
+                                ui.vertical(|ui| draw_ammo(ui, weapon, vanilla::ammo(id)));
                             });
-                            ui.vertical(|ui| {
-                                ui.horizontal(|ui| {
-                                    ui.label("ammo");
-                                    ui.add(DragValue::new(&mut weapon.ammo));
-                                });
-                                ui.add_space(10.);
-                                ui.horizontal(|ui| {
-                                    ui.label("max ammo");
-                                    ui.add(DragValue::new(&mut weapon.max_ammo));
-                                });
-                                ui.add_space(10.);
-                                ui.horizontal(|ui| {
-                                    ui.label("exp");
-                                    ui.add(DragValue::new(&mut weapon.exp));
-                                });
-                            });
-                        });
-                    }
-                });
+                        }
+                    })
+                    .response;
+                slots::drop_target(ui, &response, "weapon", pos, &mut action);
+            }
+        });
+    }
+
+    if let Some(action) = action {
+        action.apply(
+            &mut weapon[..MAX_WEAPON_NUM],
+            &mut unknown[..MAX_WEAPON_NUM],
+            weapon_num,
+            |id| {
+                let (classification, unknown) = WeaponType::with_unknown(id);
+                let weapon = Weapon {
+                    classification,
+                    level: 1,
+                    ..Weapon::default()
+                };
+                (weapon, unknown)
+            },
+        );
+    }
+
+    traded
+}
+
+/// "Trade" menu of a slot, listing the trades its weapon can go through.
+fn draw_trades(
+    ui: &mut Ui,
+    pos: usize,
+    id: i32,
+    trades: &[Trade],
+    names: Option<&NameTable>,
+    traded: &mut Option<(usize, usize)>,
+) {
+    if !trades.iter().any(|t| t.from == id) {
+        return;
+    }
+    ui.menu_button("Trade", |ui| {
+        for (i, trade) in trades.iter().enumerate().filter(|(_, t)| t.from == id) {
+            let text = format!("→ {}", name_of::<WeaponType>(trade.to, names));
+            if ui
+                .button(text)
+                .on_hover_text(trade::describe(trade, names))
+                .clicked()
+            {
+                *traded = Some((pos, i));
+                ui.close();
+            }
+        }
+    });
+}
+
+/// Move whole levels between `exp` and `level`, so the EXP fits in its level.
+fn level_up(weapon: &mut Weapon, levels: [i32; 3]) {
+    weapon.level = weapon.level.clamp(1, 3);
+    while weapon.level < 3 && weapon.exp >= levels[weapon.level as usize - 1] {
+        weapon.exp -= levels[weapon.level as usize - 1];
+        weapon.level += 1;
+    }
+    while weapon.level > 1 && weapon.exp < 0 {
+        weapon.level -= 1;
+        weapon.exp += levels[weapon.level as usize - 1];
+    }
+    weapon.exp = weapon.exp.clamp(0, levels[weapon.level as usize - 1]);
+}
+
+/// Level and EXP; `levels` is the EXP held by each level, when the weapon is known.
+fn draw_level(ui: &mut Ui, weapon: &mut Weapon, levels: Option<[i32; 3]>) {
+    let Some(levels) = levels else {
+        ui.horizontal(|ui| {
+            ui.label("level");
+            ui.add(Slider::new(&mut weapon.level, 0..=3).clamping(SliderClamping::Edits));
+        });
+        ui.horizontal(|ui| {
+            ui.label("exp");
+            ui.add(DragValue::new(&mut weapon.exp));
+        });
+        return;
+    };
+
+    ui.horizontal(|ui| {
+        ui.label("level");
+        let slider = Slider::new(&mut weapon.level, 1..=3).clamping(SliderClamping::Edits);
+        if ui.add(slider).changed() {
+            let max = levels[weapon.level as usize - 1];
+            weapon.exp = weapon.exp.clamp(0, max);
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.label("exp");
+        if ui.add(DragValue::new(&mut weapon.exp)).changed() {
+            level_up(weapon, levels);
+        }
+    });
+    ui.horizontal(|ui| {
+        for (level, &max) in (1..).zip(&levels) {
+            let fill = match weapon.level.cmp(&level) {
+                Ordering::Greater => 1.,
+                Ordering::Equal if max > 0 => weapon.exp as f32 / max as f32,
+                Ordering::Equal => 1.,
+                Ordering::Less => 0.,
+            };
+            ui.add(
+                ProgressBar::new(fill.clamp(0., 1.))
+                    .desired_width(40.)
+                    .text(format!("Lv{level}")),
+            )
+            .on_hover_text(format!("{max} EXP"));
+        }
+    });
+    ui.horizontal(|ui| {
+        if ui.button("Max level").clicked() {
+            weapon.level = 3;
+            weapon.exp = levels[2];
+        }
+        if ui.button("Reset to Lv1").clicked() {
+            weapon.level = 1;
+            weapon.exp = 0;
+        }
+    });
+}
+
+/// Ammo and max ammo; a max of 0 is infinite ammo. `ammo` is the max of a fresh weapon
+/// and what a missile expansion adds, for known weapons using ammo.
+fn draw_ammo(ui: &mut Ui, weapon: &mut Weapon, ammo: Option<(i32, i32)>) {
+    let mut infinite = weapon.max_ammo == 0;
+    if ui
+        .checkbox(&mut infinite, "infinite ammo")
+        .on_hover_text("weapons without max ammo never run out")
+        .changed()
+    {
+        weapon.max_ammo = if infinite {
+            0
+        } else {
+            ammo.map_or(100, |(max, _)| max)
+        };
+        weapon.ammo = weapon.max_ammo;
+    }
+    if infinite {
+        if ammo.is_some() {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                "⚠ this weapon normally uses ammo",
+            );
+        }
+        return;
+    }
+
+    ui.horizontal(|ui| {
+        ui.label("ammo");
+        ui.add(
+            DragValue::new(&mut weapon.ammo)
+                .range(0..=i32::MAX)
+                .clamp_existing_to_range(false),
+        );
+        ui.label("/");
+        ui.add(
+            DragValue::new(&mut weapon.max_ammo)
+                .range(1..=i32::MAX)
+                .clamp_existing_to_range(false),
+        );
+    });
+
+    if let Some((base, step)) = ammo.filter(|&(_, step)| step > 0) {
+        ui.horizontal(|ui| {
+            let expansions = (weapon.max_ammo - base) / step;
+            ui.label(format!("expansions: {expansions}"));
+            if ui.button(format!("-{step}")).clicked() && weapon.max_ammo - step >= base {
+                weapon.max_ammo -= step;
+                weapon.ammo = weapon.ammo.min(weapon.max_ammo);
+            }
+            if ui.button(format!("+{step}")).clicked() {
+                // an expansion also refills what it adds
+                weapon.max_ammo += step;
+                weapon.ammo += step;
+            }
+        });
+        if (weapon.max_ammo - base) % step != 0 {
+            ui.label(format!(
+                "max ammo is usually {base} plus {step} per expansion"
+            ));
+        }
+    }
+
+    if weapon.ammo > weapon.max_ammo {
+        ui.horizontal(|ui| {
+            ui.colored_label(ui.visuals().warn_fg_color, "⚠ more ammo than the maximum");
+            if ui.button("clamp").clicked() {
+                weapon.ammo = weapon.max_ammo;
             }
         });
     }
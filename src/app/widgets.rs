@@ -0,0 +1,226 @@
+use std::{any::type_name, hash::Hash, sync::Arc};
+
+use egui::{Button, Context, DragValue, Id, PopupCloseBehavior, Ui, WidgetText};
+
+use super::data::{NameTable, Named};
+use super::icons::Sheet;
+use super::picker;
+use super::utils::raw::GameId;
+
+/// Display name of an id which may not be known to the editor,
+/// preferring the names from the data directory.
+pub fn id_label<T: Named>(value: T, unknown: Option<i32>, names: Option<&NameTable>) -> String {
+    let id = unknown.unwrap_or(value.id());
+    match (names.and_then(|n| T::custom_name(n, id)), unknown) {
+        (Some(name), _) => name.to_owned(),
+        (None, Some(id)) => format!("Unknown #{id}"),
+        (None, None) => {
+            T::builtin_name(value.id()).map_or_else(|| value.to_string(), str::to_owned)
+        }
+    }
+}
+
+/// Display name of a numeric id.
+pub fn name_of<T: Named>(id: i32, names: Option<&NameTable>) -> String {
+    match T::from_id(id) {
+        Some(value) => id_label(value, None, names),
+        None => names
+            .and_then(|n| T::custom_name(n, id))
+            .map_or_else(|| format!("Unknown #{id}"), str::to_owned),
+    }
+}
+
+/// Set `value` from a numeric id, keeping it in `unknown` if there's no such variant.
+pub fn set_id<T: GameId>(value: &mut T, unknown: &mut Option<i32>, id: i32) {
+    match T::from_id(id) {
+        Some(known) => {
+            *value = known;
+            *unknown = None;
+        }
+        None => *unknown = Some(id),
+    }
+}
+
+/// Description of an id, with the events giving it and their flags.
+pub fn details<T: Named>(id: i32, names: Option<&NameTable>) -> Option<String> {
+    let details = names.and_then(|n| T::details(n, id));
+    let description = details
+        .map(|d| d.description.as_str())
+        .filter(|d| !d.is_empty())
+        .or_else(|| T::builtin_description(id));
+
+    let mut lines: Vec<String> = description.map(str::to_owned).into_iter().collect();
+    for source in details.into_iter().flat_map(|d| &d.sources) {
+        let mut line = format!("given by {} #{:04}", source.script, source.event);
+        if !source.flags.is_empty() {
+            let flags: Vec<String> = source.flags.iter().map(|f| format!("{f:04}")).collect();
+            line += &format!(", flags {}", flags.join(" "));
+        }
+        lines.push(line);
+    }
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+pub struct IdOption {
+    pub id: i32,
+    pub name: String,
+    /// Hover text, see [`details`].
+    pub details: Option<String>,
+}
+
+/// Options of a combo box: the variants of `T`, plus the ids named in the data directory.
+///
+/// Built once per data directory and kept in the egui memory.
+pub fn id_options<T: Named>(
+    ctx: &Context,
+    names: Option<&NameTable>,
+    skip_none: bool,
+) -> Arc<[IdOption]> {
+    let id = Id::new((
+        "id-options",
+        type_name::<T>(),
+        names.map(|n| n.serial),
+        skip_none,
+    ));
+    if let Some(options) = ctx.data(|d| d.get_temp::<Arc<[IdOption]>>(id)) {
+        return options;
+    }
+    let options: Arc<[IdOption]> = build_options::<T>(names, skip_none).into();
+    ctx.data_mut(|d| d.insert_temp(id, options.clone()));
+    options
+}
+
+fn build_options<T: Named>(names: Option<&NameTable>, skip_none: bool) -> Vec<IdOption> {
+    let custom = names.map(T::custom_ids).unwrap_or_default();
+    let mut ids: Vec<i32> = if T::REPLACES_BUILTIN && !custom.is_empty() {
+        custom
+    } else {
+        T::iter().map(T::id).chain(custom).collect()
+    };
+    ids.sort_unstable();
+    ids.dedup();
+
+    ids.into_iter()
+        .filter(|&id| !(skip_none && id == 0))
+        .map(|id| IdOption {
+            id,
+            name: name_of::<T>(id, names),
+            details: details::<T>(id, names),
+        })
+        .collect()
+}
+
+/// Hover text of an option, if it has some.
+fn with_details(response: egui::Response, option: Option<&IdOption>) -> egui::Response {
+    match option.and_then(|o| o.details.as_deref()) {
+        Some(details) => response.on_hover_text(details),
+        None => response,
+    }
+}
+
+/// Combo box over id options, followed by a numeric field accepting any id.
+pub fn id_combo<T: GameId>(
+    ui: &mut Ui,
+    id_salt: impl Hash,
+    selected_text: impl Into<WidgetText>,
+    width: f32,
+    value: &mut T,
+    unknown: &mut Option<i32>,
+    options: &[IdOption],
+) {
+    let current = unknown.unwrap_or(value.id());
+    let id = Id::new(&id_salt);
+    let response = egui::ComboBox::new(id_salt, "")
+        .selected_text(selected_text)
+        .width(width)
+        .close_behavior(PopupCloseBehavior::CloseOnClickOutside)
+        .show_ui(ui, |ui| {
+            let picked = picker::show::<T>(ui, id, current, options, 1, |ui, option, selected| {
+                with_details(ui.selectable_label(selected, &option.name), Some(option))
+            });
+            if let Some(picked) = picked {
+                set_id(value, unknown, picked);
+            }
+        })
+        .response;
+    with_details(response, options.iter().find(|o| o.id == current));
+
+    id_field(ui, current, value, unknown);
+}
+
+/// Picker of the id of a new slot, which can't be the empty id.
+pub fn pick_id<T: Named>(
+    ui: &mut Ui,
+    id_salt: impl Hash,
+    names: Option<&NameTable>,
+) -> Option<i32> {
+    let options = id_options::<T>(ui.ctx(), names, true);
+    picker::show::<T>(
+        ui,
+        Id::new(id_salt),
+        0,
+        &options,
+        1,
+        |ui, option, selected| {
+            with_details(ui.selectable_label(selected, &option.name), Some(option))
+        },
+    )
+}
+
+/// Like [`id_combo`], showing the current icon and picking from a grid of icons.
+pub fn icon_combo<T: GameId>(
+    ui: &mut Ui,
+    id_salt: impl Hash,
+    width: f32,
+    value: &mut T,
+    unknown: &mut Option<i32>,
+    options: &[IdOption],
+    sheet: &Sheet,
+) {
+    let current = unknown.unwrap_or(value.id());
+    let selected = options.iter().find(|o| o.id == current);
+    let selected_text = selected.map_or_else(|| format!("Unknown #{current}"), |o| o.name.clone());
+
+    if let Some(image) = sheet.image(current) {
+        with_details(ui.add(image), selected);
+    }
+    let id = Id::new(&id_salt);
+    let response = egui::ComboBox::new(id_salt, "")
+        .selected_text(selected_text)
+        .width(width)
+        .close_behavior(PopupCloseBehavior::CloseOnClickOutside)
+        .show_ui(ui, |ui| {
+            let columns = sheet.picker_columns;
+            let picked =
+                picker::show::<T>(ui, id, current, options, columns, |ui, option, selected| {
+                    let button = match sheet.image(option.id) {
+                        Some(image) => Button::image(image),
+                        None => Button::new(&option.name),
+                    };
+                    let hover = match &option.details {
+                        Some(details) => format!("{}\n{details}", option.name),
+                        None => option.name.clone(),
+                    };
+                    ui.add(button.selected(selected)).on_hover_text(hover)
+                });
+            if let Some(picked) = picked {
+                set_id(value, unknown, picked);
+            }
+        })
+        .response;
+    with_details(response, selected);
+
+    id_field(ui, current, value, unknown);
+}
+
+/// Numeric field accepting any id.
+fn id_field<T: GameId>(ui: &mut Ui, current: i32, value: &mut T, unknown: &mut Option<i32>) {
+    let mut id = current;
+    if ui
+        .add(DragValue::new(&mut id).prefix("#"))
+        .on_hover_text("numeric id, for values added by mods")
+        .changed()
+    {
+        set_id(value, unknown, id);
+    }
+}